@@ -1,9 +1,13 @@
 MEMORY {
     BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
-    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+    /* the last 16K hold the replays, see src/flash.rs */
+    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100 - 16K
     RAM   : ORIGIN = 0x20000000, LENGTH = 256K
 }
 
+/* checked against the flash size of the board at boot */
+__replays_start = ORIGIN(FLASH) + LENGTH(FLASH);
+
 EXTERN(BOOT2_FIRMWARE)
 
 SECTIONS {
@@ -0,0 +1,67 @@
+use crate::game::{Screen, View};
+use crate::input::Button;
+use core::cmp::Ordering;
+
+// plays the game by itself, one press per tick at most
+pub fn press(view: &View) -> Option<Button> {
+    match view.screen() {
+        Screen::Start => None,
+        // move over to the lane that turns towards the goal
+        Screen::Travel => {
+            let travel = view.travel();
+            match travel.lane_towards_goal().cmp(&travel.active_lane()) {
+                Ordering::Less => Some(Button::Up),
+                Ordering::Greater => Some(Button::Down),
+                Ordering::Equal => None,
+            }
+        }
+        // set each pin as soon as it gets close to the shear line
+        Screen::Lock => {
+            let lock = view.lock();
+            (!lock.open && lock.active_pin().is_near_shear()).then_some(Button::Action)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn plays_thousands_of_games() {
+        let mut slowest = 0;
+        for entropy in 0..2000 {
+            let mut game = Game::new(entropy);
+            // the start screen ignores presses for a moment
+            for _ in 0..3 {
+                game.tick();
+                game.transition();
+            }
+            game.button_action();
+
+            let (mut score, mut since, mut locks) = (0, 0, 0);
+            for _ in 0..3000 {
+                match press(game.view()) {
+                    Some(Button::Action) => game.button_action(),
+                    Some(Button::Up) => game.button_up(),
+                    Some(Button::Down) => game.button_down(),
+                    None => (),
+                }
+                game.tick();
+                game.transition();
+                // it never loses
+                assert!(!matches!(game.screen(), Screen::Start), "game {entropy}");
+
+                since += 1;
+                if game.score() > score {
+                    score = game.score();
+                    slowest = slowest.max(since);
+                    (since, locks) = (0, locks + 1);
+                }
+            }
+            assert!(locks >= 5, "game {entropy} only opened {locks} locks");
+        }
+        println!("at most {slowest} ticks per lock");
+    }
+}
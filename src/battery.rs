@@ -0,0 +1,180 @@
+const ADC_REFERENCE_MV: u32 = 3300;
+const ADC_MAX: u32 = 4095;
+
+// exponential moving average, every sample moves the value by 1/16th
+const SMOOTHING_SHIFT: u32 = 4;
+
+const LOW_PERCENT: u8 = 10;
+// shut down well before the regulator browns out
+const CRITICAL_MV: u16 = 3350;
+// nothing would run this low, the pin is floating or the board has no battery
+const MISSING_MV: u16 = 2500;
+
+// rough lipo discharge curve, millivolts to percent
+const CURVE: [(u16, u8); 10] = [
+    (3300, 0),
+    (3500, 5),
+    (3600, 10),
+    (3700, 25),
+    (3750, 40),
+    (3800, 55),
+    (3850, 65),
+    (3950, 80),
+    (4050, 90),
+    (4200, 100),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    // running from usb
+    Missing,
+    Ok,
+    Low,
+    Critical,
+}
+
+// the divider depends on the hardware, see `BATTERY_DIVIDER`
+pub const fn adc_to_millivolts(raw: u16, divider: u32) -> u16 {
+    (raw as u32 * ADC_REFERENCE_MV * divider / ADC_MAX) as u16
+}
+
+pub const fn percent(mv: u16) -> u8 {
+    if mv <= CURVE[0].0 {
+        return CURVE[0].1;
+    }
+
+    let mut i = 1;
+    while i < CURVE.len() {
+        let (high_mv, high_percent) = CURVE[i];
+        if mv < high_mv {
+            let (low_mv, low_percent) = CURVE[i - 1];
+            let span = (high_percent - low_percent) as u32;
+            let progress = (mv - low_mv) as u32 * span / (high_mv - low_mv) as u32;
+            return low_percent + progress as u8;
+        }
+        i += 1;
+    }
+
+    CURVE[CURVE.len() - 1].1
+}
+
+pub struct Battery {
+    divider: u32,
+    // millivolts, shifted left by SMOOTHING_SHIFT
+    filtered: u32,
+}
+
+impl Battery {
+    pub const fn new(divider: u32) -> Self {
+        Self {
+            divider,
+            filtered: 0,
+        }
+    }
+
+    pub const fn sample(&mut self, raw: u16) {
+        let mv = adc_to_millivolts(raw, self.divider);
+        let missing = mv < MISSING_MV;
+        let mv = (mv as u32) << SMOOTHING_SHIFT;
+        if missing != (self.millivolts() < MISSING_MV) {
+            // start with the first reading instead of slowly rising from zero, same when a
+            // battery gets connected or removed, it mustn't pass through critical on the way
+            self.filtered = mv;
+        } else {
+            self.filtered =
+                self.filtered - (self.filtered >> SMOOTHING_SHIFT) + (mv >> SMOOTHING_SHIFT);
+        }
+    }
+
+    pub const fn millivolts(&self) -> u16 {
+        (self.filtered >> SMOOTHING_SHIFT) as u16
+    }
+
+    pub const fn percent(&self) -> u8 {
+        percent(self.millivolts())
+    }
+
+    pub const fn level(&self) -> Level {
+        // also before the first reading
+        if self.millivolts() < MISSING_MV {
+            Level::Missing
+        } else if self.millivolts() <= CRITICAL_MV {
+            Level::Critical
+        } else if self.percent() <= LOW_PERCENT {
+            Level::Low
+        } else {
+            Level::Ok
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // what the adc reads for a voltage behind a 1:2 divider
+    const fn raw(mv: u32) -> u16 {
+        (mv * ADC_MAX / ADC_REFERENCE_MV / 2) as u16
+    }
+
+    #[test]
+    fn converts_adc_readings() {
+        assert_eq!(adc_to_millivolts(0, 2), 0);
+        assert_eq!(adc_to_millivolts(ADC_MAX as u16, 2), 6600);
+        assert_eq!(adc_to_millivolts(ADC_MAX as u16, 3), 9900);
+        assert!(adc_to_millivolts(raw(3700), 2).abs_diff(3700) <= 2);
+    }
+
+    #[test]
+    fn follows_the_curve() {
+        assert_eq!(percent(0), 0);
+        assert_eq!(percent(3300), 0);
+        assert_eq!(percent(3400), 2);
+        assert_eq!(percent(3600), 10);
+        assert_eq!(percent(3725), 32);
+        assert_eq!(percent(4200), 100);
+        assert_eq!(percent(5000), 100);
+        for mv in 3000..4300 {
+            assert!(percent(mv) <= percent(mv + 1));
+        }
+    }
+
+    #[test]
+    fn smooths_readings() {
+        let mut battery = Battery::new(2);
+        battery.sample(raw(4000));
+        let first = battery.millivolts();
+        assert!(first.abs_diff(4000) <= 2);
+
+        // a single dip only moves it by a 16th
+        battery.sample(raw(3600));
+        assert!(battery.millivolts().abs_diff(first - 25) <= 2);
+
+        for _ in 0..200 {
+            battery.sample(raw(3600));
+        }
+        assert!(battery.millivolts().abs_diff(3600) <= 20);
+        assert!(battery.level() == Level::Low);
+    }
+
+    #[test]
+    fn empty_battery_is_critical() {
+        let mut battery = Battery::new(2);
+        battery.sample(raw(3300));
+        assert!(battery.level() == Level::Critical);
+    }
+
+    #[test]
+    fn no_battery_is_not_critical() {
+        let mut battery = Battery::new(2);
+        assert!(battery.level() == Level::Missing);
+        for mv in [0, 40, 900, 300] {
+            battery.sample(raw(mv));
+            assert!(battery.level() == Level::Missing);
+        }
+
+        // plugging one in goes straight to its charge
+        battery.sample(raw(3900));
+        assert!(battery.level() == Level::Ok);
+    }
+}
@@ -1,11 +1,7 @@
-use eh0::blocking::i2c;
-use sh1106::{prelude::*, Builder};
+use crate::board;
+use crate::oled::{Interface, Oled};
 
-pub fn init<T: i2c::Write>(i2c: T) -> GraphicsMode<I2cInterface<T>> {
-    let mut display: GraphicsMode<_> = Builder::new()
-        .with_rotation(DisplayRotation::Rotate180)
-        .connect_i2c(i2c)
-        .into();
-    display.init().ok();
-    display
+// the display itself is initialized by `Panel::probe`
+pub const fn init<DI: Interface>(interface: DI) -> Oled<DI> {
+    Oled::new(interface, board::BIG_DISPLAY, board::BIG_ROTATION)
 }
@@ -0,0 +1,122 @@
+// a starting point for your own pcb, change whatever doesn't match
+use super::{Board, Buses, I2cPin};
+use crate::oled::{self, Controller, Rotation};
+use display_interface_i2c::I2CInterface;
+use display_interface_spi::SPIInterface;
+use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
+use fugit::{HertzU32, RateExtU32};
+use hal::dma::{Channel, CH0};
+use hal::gpio::{bank0::*, FunctionSioOutput, FunctionSpi, Pin, PullDown};
+use hal::i2c::I2C;
+use hal::pac;
+use hal::pwm::{FreeRunning, Pwm7, Slice, Slices};
+use hal::spi::{Enabled, Spi};
+pub use rp2040_hal as hal;
+pub use rp2040_hal::{entry, gpio::Pins};
+
+// the second stage bootloader has to match the flash chip
+#[link_section = ".boot2"]
+#[used]
+pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
+
+pub const XOSC_CRYSTAL_FREQ: u32 = 12_000_000;
+
+pub type ActionPin = Gpio8;
+pub type UpPin = Gpio27;
+pub type DownPin = Gpio15;
+// needs to be channel a of the buzzer pwm slice
+pub type BuzzerPin = Gpio14;
+pub type BuzzerPwm = Pwm7;
+pub type BatteryPin = Gpio29;
+pub type LedPin = Gpio16;
+
+type SpiPin<I> = Pin<I, FunctionSpi, PullDown>;
+type OutputPin<I> = Pin<I, FunctionSioOutput, PullDown>;
+
+// the small display goes on i2c0, the big one on spi1, other buses have to be added to `Buses`
+// tie the reset pin of the spi display high
+pub struct DisplayPins {
+    small_sda: I2cPin<Gpio12>,
+    small_scl: I2cPin<Gpio13>,
+    big_sck: SpiPin<Gpio10>,
+    big_mosi: SpiPin<Gpio11>,
+    big_cs: OutputPin<Gpio9>,
+    big_dc: OutputPin<Gpio7>,
+}
+
+pub type SmallInterface = I2CInterface<I2C<pac::I2C0, (I2cPin<Gpio12>, I2cPin<Gpio13>)>>;
+pub type BigInterface = SPIInterface<
+    ExclusiveDevice<
+        Spi<Enabled, pac::SPI1, (SpiPin<Gpio11>, SpiPin<Gpio10>)>,
+        OutputPin<Gpio9>,
+        NoDelay,
+    >,
+    OutputPin<Gpio7>,
+>;
+// no dma for spi yet, the big display blocks while flushing
+pub type BigDma = BigInterface;
+
+pub const SMALL_I2C_KHZ: u32 = 400;
+pub const BIG_SPI_MHZ: u32 = 8;
+
+pub const SMALL_DISPLAY: &Controller = &oled::SSD1306;
+pub const SMALL_ROTATION: Rotation = Rotation::Normal;
+pub const BIG_DISPLAY: &Controller = &oled::SSD1309;
+pub const BIG_ROTATION: Rotation = Rotation::UpsideDown;
+
+pub const BATTERY_DIVIDER: u32 = 2;
+
+// the size of your flash chip, change the FLASH length in memory.x along with it
+pub const FLASH_SIZE: u32 = 2048 * 1024;
+
+pub fn split(pins: Pins) -> Board {
+    Board {
+        action: pins.gpio8.reconfigure(),
+        up: pins.gpio27.reconfigure(),
+        down: pins.gpio15.reconfigure(),
+        displays: DisplayPins {
+            small_sda: pins.gpio12.reconfigure(),
+            small_scl: pins.gpio13.reconfigure(),
+            big_sck: pins.gpio10.reconfigure(),
+            big_mosi: pins.gpio11.reconfigure(),
+            big_cs: pins.gpio9.reconfigure(),
+            big_dc: pins.gpio7.reconfigure(),
+        },
+        buzzer: pins.gpio14,
+        battery: pins.gpio29.reconfigure(),
+        led: pins.gpio16.reconfigure(),
+    }
+}
+
+pub const fn buzzer_pwm(slices: Slices) -> Slice<BuzzerPwm, FreeRunning> {
+    slices.pwm7
+}
+
+pub fn displays(
+    pins: DisplayPins,
+    buses: Buses,
+    resets: &mut pac::RESETS,
+    clock: HertzU32,
+) -> (SmallInterface, BigInterface) {
+    let small = I2C::i2c0(
+        buses.i2c0,
+        pins.small_sda,
+        pins.small_scl,
+        SMALL_I2C_KHZ.kHz(),
+        resets,
+        clock,
+    );
+    let big = Spi::<_, _, _, 8>::new(buses.spi1, (pins.big_mosi, pins.big_sck)).init(
+        resets,
+        clock,
+        BIG_SPI_MHZ.MHz(),
+        embedded_hal::spi::MODE_0,
+    );
+    // setting a gpio can't fail
+    let big = ExclusiveDevice::new_no_delay(big, pins.big_cs).unwrap();
+    (oled::i2c(small), SPIInterface::new(big, pins.big_dc))
+}
+
+pub const fn big_dma(interface: BigInterface, _channel: Channel<CH0>) -> BigDma {
+    interface
+}
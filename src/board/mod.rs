@@ -0,0 +1,52 @@
+// pin assignments and settings of the boards we know about, pick one with a cargo feature
+#[cfg(feature = "rp2040-zero")]
+mod rp2040_zero;
+#[cfg(feature = "rp2040-zero")]
+pub use rp2040_zero::*;
+
+#[cfg(feature = "pico")]
+mod pico;
+#[cfg(feature = "pico")]
+pub use pico::*;
+
+#[cfg(feature = "custom")]
+mod custom;
+#[cfg(feature = "custom")]
+pub use custom::*;
+
+#[cfg(not(any(feature = "rp2040-zero", feature = "pico", feature = "custom")))]
+compile_error!("select a board with one of the features: rp2040-zero, pico, custom");
+
+#[cfg(any(
+    all(feature = "rp2040-zero", feature = "pico"),
+    all(feature = "rp2040-zero", feature = "custom"),
+    all(feature = "pico", feature = "custom"),
+))]
+compile_error!("only one board feature can be enabled, use --no-default-features");
+
+use self::hal::gpio::{
+    FunctionI2C, FunctionNull, FunctionPio0, FunctionSioInput, Pin, PullDown, PullNone, PullUp,
+};
+use self::hal::pac;
+
+pub type Button<I> = Pin<I, FunctionSioInput, PullUp>;
+pub type I2cPin<I> = Pin<I, FunctionI2C, PullUp>;
+
+pub struct Board {
+    pub action: Button<ActionPin>,
+    pub up: Button<UpPin>,
+    pub down: Button<DownPin>,
+    pub displays: DisplayPins,
+    pub buzzer: Pin<BuzzerPin, FunctionNull, PullDown>,
+    pub battery: Pin<BatteryPin, FunctionSioInput, PullNone>,
+    pub led: Pin<LedPin, FunctionPio0, PullDown>,
+}
+
+// the buses the profile wires the displays to, see `displays`, the others stay unclaimed
+pub struct Buses {
+    pub i2c0: pac::I2C0,
+    #[cfg(any(feature = "rp2040-zero", feature = "pico"))]
+    pub i2c1: pac::I2C1,
+    #[cfg(feature = "custom")]
+    pub spi1: pac::SPI1,
+}
@@ -0,0 +1,99 @@
+use super::{Board, Buses, I2cPin};
+use crate::dma::I2cDma;
+use crate::oled::{self, Controller, Rotation};
+use display_interface_i2c::I2CInterface;
+use fugit::{HertzU32, RateExtU32};
+use hal::dma::{Channel, CH0};
+use hal::gpio::bank0::*;
+use hal::i2c::I2C;
+use hal::pac;
+use hal::pwm::{FreeRunning, Pwm7, Slice, Slices};
+pub use rp_pico::{entry, hal, Pins, XOSC_CRYSTAL_FREQ};
+
+// same gpio numbers as the rp2040-zero, so the wiring carries over
+pub type ActionPin = Gpio8;
+pub type UpPin = Gpio27;
+pub type DownPin = Gpio15;
+// needs to be channel a of the buzzer pwm slice
+pub type BuzzerPin = Gpio14;
+pub type BuzzerPwm = Pwm7;
+pub type BatteryPin = Gpio29;
+// the pico has no rgb led, connect an external ws2812
+pub type LedPin = Gpio16;
+
+// the small display goes on i2c0, the big one on i2c1
+pub struct DisplayPins {
+    small_sda: I2cPin<Gpio12>,
+    small_scl: I2cPin<Gpio13>,
+    big_sda: I2cPin<Gpio10>,
+    big_scl: I2cPin<Gpio11>,
+}
+
+pub type SmallInterface = I2CInterface<I2C<pac::I2C0, (I2cPin<Gpio12>, I2cPin<Gpio13>)>>;
+pub type BigInterface = I2CInterface<I2C<pac::I2C1, (I2cPin<Gpio10>, I2cPin<Gpio11>)>>;
+// the big display gets its frames by dma
+pub type BigDma = I2cDma<pac::I2C1, (I2cPin<Gpio10>, I2cPin<Gpio11>), CH0>;
+
+pub const SMALL_I2C_KHZ: u32 = 400;
+pub const BIG_I2C_KHZ: u32 = 400;
+
+pub const SMALL_DISPLAY: &Controller = &oled::SSD1306;
+pub const SMALL_ROTATION: Rotation = Rotation::Normal;
+pub const BIG_DISPLAY: &Controller = &oled::SH1106;
+pub const BIG_ROTATION: Rotation = Rotation::UpsideDown;
+
+// the pico measures vsys through its own 1:3 voltage divider
+pub const BATTERY_DIVIDER: u32 = 3;
+
+// the w25q16jv on the pico, memory.x has to agree
+pub const FLASH_SIZE: u32 = 2048 * 1024;
+
+pub fn split(pins: Pins) -> Board {
+    Board {
+        action: pins.gpio8.reconfigure(),
+        up: pins.gpio27.reconfigure(),
+        down: pins.gpio15.reconfigure(),
+        displays: DisplayPins {
+            small_sda: pins.gpio12.reconfigure(),
+            small_scl: pins.gpio13.reconfigure(),
+            big_sda: pins.gpio10.reconfigure(),
+            big_scl: pins.gpio11.reconfigure(),
+        },
+        buzzer: pins.gpio14,
+        battery: pins.voltage_monitor.reconfigure(),
+        led: pins.gpio16.reconfigure(),
+    }
+}
+
+pub const fn buzzer_pwm(slices: Slices) -> Slice<BuzzerPwm, FreeRunning> {
+    slices.pwm7
+}
+
+pub fn displays(
+    pins: DisplayPins,
+    buses: Buses,
+    resets: &mut pac::RESETS,
+    clock: HertzU32,
+) -> (SmallInterface, BigInterface) {
+    let small = I2C::i2c0(
+        buses.i2c0,
+        pins.small_sda,
+        pins.small_scl,
+        SMALL_I2C_KHZ.kHz(),
+        resets,
+        clock,
+    );
+    let big = I2C::i2c1(
+        buses.i2c1,
+        pins.big_sda,
+        pins.big_scl,
+        BIG_I2C_KHZ.kHz(),
+        resets,
+        clock,
+    );
+    (oled::i2c(small), oled::i2c(big))
+}
+
+pub fn big_dma(interface: BigInterface, channel: Channel<CH0>) -> BigDma {
+    I2cDma::new(interface, channel)
+}
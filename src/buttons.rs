@@ -0,0 +1,88 @@
+use crate::board::{self, hal, ActionPin, DownPin, UpPin};
+use crate::input::{Button, Debounce, Queue};
+use core::cell::RefCell;
+use cortex_m::interrupt::{self as critical, Mutex};
+use embedded_hal::digital::InputPin;
+use hal::gpio::{Interrupt, PinId};
+use hal::pac::{self, interrupt};
+
+// every press since the main loop last looked, in order
+pub static PRESSES: Queue = Queue::new();
+
+struct Pins {
+    action: board::Button<ActionPin>,
+    up: board::Button<UpPin>,
+    down: board::Button<DownPin>,
+}
+
+static PINS: Mutex<RefCell<Option<Pins>>> = Mutex::new(RefCell::new(None));
+
+// hands the buttons over to the gpio interrupt
+pub fn init(
+    action: board::Button<ActionPin>,
+    up: board::Button<UpPin>,
+    down: board::Button<DownPin>,
+) {
+    // releases matter for the debounce too
+    for edge in [Interrupt::EdgeLow, Interrupt::EdgeHigh] {
+        action.set_interrupt_enabled(edge, true);
+        up.set_interrupt_enabled(edge, true);
+        down.set_interrupt_enabled(edge, true);
+    }
+    critical::free(|cs| PINS.borrow(cs).replace(Some(Pins { action, up, down })));
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::IO_IRQ_BANK0);
+    }
+}
+
+pub fn is_held(button: Button) -> bool {
+    critical::free(|cs| {
+        let mut pins = PINS.borrow(cs).borrow_mut();
+        let Some(pins) = pins.as_mut() else {
+            return false;
+        };
+        // reading a gpio can't fail
+        match button {
+            Button::Action => pins.action.is_low().unwrap(),
+            Button::Up => pins.up.is_low().unwrap(),
+            Button::Down => pins.down.is_low().unwrap(),
+        }
+    })
+}
+
+// any button wakes us up from dormant sleep
+pub fn enable_dormant_wake() {
+    critical::free(|cs| {
+        if let Some(pins) = PINS.borrow(cs).borrow_mut().as_mut() {
+            pins.action
+                .set_dormant_wake_enabled(Interrupt::EdgeLow, true);
+            pins.up.set_dormant_wake_enabled(Interrupt::EdgeLow, true);
+            pins.down.set_dormant_wake_enabled(Interrupt::EdgeLow, true);
+        }
+    });
+}
+
+fn edges<I: PinId>(pin: &mut board::Button<I>, button: Button, at: u32, debounce: &mut Debounce) {
+    for (edge, pressed) in [(Interrupt::EdgeLow, true), (Interrupt::EdgeHigh, false)] {
+        if pin.interrupt_status(edge) {
+            pin.clear_interrupt(edge);
+            if let Some(press) = debounce.edge(button, pressed, at) {
+                PRESSES.push(press);
+            }
+        }
+    }
+}
+
+#[interrupt]
+fn IO_IRQ_BANK0() {
+    static mut DEBOUNCE: Debounce = Debounce::new();
+
+    let at = unsafe { (*pac::TIMER::ptr()).timerawl().read().bits() };
+    critical::free(|cs| {
+        if let Some(pins) = PINS.borrow(cs).borrow_mut().as_mut() {
+            edges(&mut pins.action, Button::Action, at, DEBOUNCE);
+            edges(&mut pins.up, Button::Up, at, DEBOUNCE);
+            edges(&mut pins.down, Button::Down, at, DEBOUNCE);
+        }
+    });
+}
@@ -0,0 +1,48 @@
+use crate::board::hal::{
+    gpio::AnyPin,
+    pwm::{FreeRunning, Slice, SliceId, ValidPwmOutputPin, A},
+};
+use crate::sound::{self, Tone};
+use embedded_hal::pwm::SetDutyCycle;
+use fugit::HertzU32;
+
+// keep the counter slow enough that low notes still fit into 16 bits
+const CLOCK_DIVIDER: u8 = 40;
+
+pub struct Buzzer<S: SliceId> {
+    pwm: Slice<S, FreeRunning>,
+    counter_freq: u32,
+}
+
+pub fn init<S: SliceId, P: AnyPin>(
+    mut pwm: Slice<S, FreeRunning>,
+    pin: P,
+    system_clock: HertzU32,
+) -> Buzzer<S>
+where
+    P::Id: ValidPwmOutputPin<S, A>,
+{
+    pwm.set_ph_correct();
+    pwm.set_div_int(CLOCK_DIVIDER);
+    pwm.channel_a.output_to(pin);
+    pwm.channel_a.set_duty_cycle(0).ok();
+    pwm.enable();
+
+    Buzzer {
+        pwm,
+        // phase correct mode counts up and down
+        counter_freq: system_clock.to_Hz() / CLOCK_DIVIDER as u32 / 2,
+    }
+}
+
+impl<S: SliceId> Tone for Buzzer<S> {
+    fn play(&mut self, freq: u16) {
+        let top = sound::pwm_top(self.counter_freq, freq);
+        self.pwm.set_top(top - 1);
+        self.pwm.channel_a.set_duty_cycle(top / 2).ok();
+    }
+
+    fn stop(&mut self) {
+        self.pwm.channel_a.set_duty_cycle(0).ok();
+    }
+}
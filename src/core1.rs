@@ -0,0 +1,180 @@
+use crate::board::hal::{
+    multicore::{Multicore, Stack},
+    pac,
+    sio::SioFifo,
+    Sio,
+};
+use crate::game::View;
+use crate::gfx::burnin::Drift;
+use crate::gfx::viewport::Layout;
+use crate::hotplug::{Hotplug, Panel};
+use crate::idle::Power;
+use core::cell::UnsafeCell;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, AtomicBool, Ordering};
+
+static mut CORE1_STACK: Stack<4096> = Stack::new();
+
+// everything core 1 needs to draw a frame of the small display
+pub struct Snapshot {
+    pub view: View,
+    pub drift: Drift,
+    // both views go on the small display while the big one is missing
+    pub single: Option<Layout>,
+}
+
+// whether the small display answers, the big one shows both views while it doesn't
+static PRESENT: AtomicBool = AtomicBool::new(false);
+
+// anything else than a slot index going through the fifo
+const SET_POWER: u32 = 1 << 8;
+const PARK: u32 = 1 << 9;
+
+const fn encode_power(power: Power) -> u32 {
+    SET_POWER
+        | match power {
+            Power::Awake => 0,
+            Power::Dimmed => 1,
+            Power::Blank => 2,
+            Power::Sleep => 3,
+        }
+}
+
+const fn decode_power(message: u32) -> Power {
+    match message & !SET_POWER {
+        0 => Power::Awake,
+        1 => Power::Dimmed,
+        2 => Power::Blank,
+        _ => Power::Sleep,
+    }
+}
+
+// core 0 fills one slot while core 1 draws the other, the fifo passes the index back and forth
+struct Slots([UnsafeCell<Option<Snapshot>>; 2]);
+
+// a slot is only ever touched by the core that currently owns its index
+unsafe impl Sync for Slots {}
+
+static SLOTS: Slots = Slots([UnsafeCell::new(None), UnsafeCell::new(None)]);
+
+pub struct Core1 {
+    fifo: SioFifo,
+    back: usize,
+    busy: bool,
+}
+
+impl Core1 {
+    // hands the display over to core 1 for good
+    pub fn spawn<P: Panel + Send + 'static>(
+        psm: &mut pac::PSM,
+        ppb: &mut pac::PPB,
+        mut fifo: SioFifo,
+        display: Hotplug<P>,
+    ) -> Self {
+        PRESENT.store(display.is_present(), Ordering::Relaxed);
+        let mut multicore = Multicore::new(psm, ppb, &mut fifo);
+        let cores = multicore.cores();
+        // we only ever spawn once, nothing else touches the stack
+        let stack = unsafe { &mut (*ptr::addr_of_mut!(CORE1_STACK)).mem };
+        cores[1].spawn(stack, move || run(display)).unwrap();
+
+        Self {
+            fifo,
+            back: 0,
+            busy: false,
+        }
+    }
+
+    // blocks until core 1 is done with the last snapshot
+    pub fn wait(&mut self) {
+        if self.busy {
+            self.fifo.read_blocking();
+            compiler_fence(Ordering::Acquire);
+            self.busy = false;
+        }
+    }
+
+    // core 1 draws the snapshot while we carry on with the next frame
+    pub fn publish(&mut self, snapshot: Snapshot) {
+        // core 1 is at most busy with the other slot
+        unsafe {
+            *SLOTS.0[self.back].get() = Some(snapshot);
+        }
+        self.wait();
+        compiler_fence(Ordering::Release);
+        self.fifo.write_blocking(self.back as u32);
+        self.busy = true;
+        self.back ^= 1;
+    }
+
+    pub fn set_power(&mut self, power: Power) {
+        self.wait();
+        self.fifo.write_blocking(encode_power(power));
+        self.busy = true;
+    }
+
+    pub fn is_present(&self) -> bool {
+        PRESENT.load(Ordering::Relaxed)
+    }
+
+    // keeps core 1 out of flash while `f` writes to it
+    pub fn parked<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        self.wait();
+        self.fifo.write_blocking(PARK);
+        self.fifo.read_blocking();
+        let result = f();
+        self.fifo.write_blocking(PARK);
+        result
+    }
+}
+
+// spins in ram until core 0 sends anything, only touching the fifo registers
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn park(fifo_st: *const u32, fifo_wr: *mut u32, fifo_rd: *const u32) {
+    ptr::write_volatile(fifo_wr, PARK);
+    // wait for a valid entry
+    while ptr::read_volatile(fifo_st) & 1 == 0 {}
+    ptr::read_volatile(fifo_rd);
+}
+
+fn run<P: Panel>(mut display: Hotplug<P>) {
+    // the fifo is all we need from here
+    let pac = unsafe { pac::Peripherals::steal() };
+    let sio = unsafe { &*pac::SIO::ptr() };
+    let mut fifo = Sio::new(pac.SIO).fifo;
+
+    loop {
+        let message = fifo.read_blocking();
+        if message == PARK {
+            // core 0 is about to write to flash, we can't run from it meanwhile
+            unsafe {
+                park(
+                    sio.fifo_st().as_ptr(),
+                    sio.fifo_wr().as_ptr(),
+                    sio.fifo_rd().as_ptr(),
+                )
+            };
+            continue;
+        }
+
+        compiler_fence(Ordering::Acquire);
+        // core 0 doesn't touch a slot until we send its index back
+        if message & SET_POWER != 0 {
+            display.set_power(decode_power(message));
+        } else if let Some(snapshot) = unsafe { &*SLOTS.0[message as usize].get() } {
+            display.tick();
+            display.render(|display| {
+                let display = &mut snapshot.drift.apply(display);
+                match snapshot.single {
+                    Some(layout) => snapshot.view.draw_single_screen(display, layout),
+                    None => snapshot.view.draw_small_screen(display),
+                }
+            });
+            PRESENT.store(display.is_present(), Ordering::Relaxed);
+        }
+
+        compiler_fence(Ordering::Release);
+        fifo.write_blocking(message);
+    }
+}
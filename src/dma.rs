@@ -0,0 +1,191 @@
+use crate::board::hal::{
+    dma::{Channel, ChannelIndex},
+    i2c::I2C,
+    pac,
+};
+use crate::oled::{self, Interface};
+use core::iter;
+use core::sync::atomic::{compiler_fence, Ordering};
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use display_interface_i2c::I2CInterface;
+use embedded_hal::i2c::I2c;
+
+// a whole frame plus the control byte and addressing commands of up to 16 pages
+pub const QUEUE_SIZE: usize = oled::BUFFER_SIZE + 16 * 5;
+
+// ends the transfer after this byte, the next one starts a new transfer to the same address
+const STOP: u16 = 1 << 9;
+
+// the registers and dma request of an i2c block
+pub trait I2cBlock {
+    const TX_DREQ: u8;
+    fn registers() -> &'static pac::i2c0::RegisterBlock;
+}
+
+impl I2cBlock for pac::I2C0 {
+    const TX_DREQ: u8 = 32;
+    fn registers() -> &'static pac::i2c0::RegisterBlock {
+        unsafe { &*pac::I2C0::ptr() }
+    }
+}
+
+impl I2cBlock for pac::I2C1 {
+    const TX_DREQ: u8 = 34;
+    fn registers() -> &'static pac::i2c0::RegisterBlock {
+        unsafe { &*pac::I2C1::ptr() }
+    }
+}
+
+// streams whole frames to an i2c display while the cpu does something else,
+// everything else still goes through the blocking driver
+pub struct I2cDma<B, P, CH: ChannelIndex> {
+    interface: I2CInterface<I2C<B, P>>,
+    _channel: Channel<CH>,
+    // the data register takes the stop bit along with each byte
+    queue: &'static mut [u16; QUEUE_SIZE],
+    len: usize,
+    busy: bool,
+}
+
+impl<B: I2cBlock, P, CH: ChannelIndex> I2cDma<B, P, CH> {
+    pub fn new(interface: I2CInterface<I2C<B, P>>, channel: Channel<CH>) -> Self {
+        B::registers().ic_dma_cr().write(|w| w.tdmae().enabled());
+        Self {
+            interface,
+            _channel: channel,
+            queue: cortex_m::singleton!(: [u16; QUEUE_SIZE] = [0; QUEUE_SIZE]).unwrap(),
+            len: 0,
+            busy: false,
+        }
+    }
+
+    fn channel() -> &'static pac::dma::CH {
+        // we own the channel
+        unsafe { (*pac::DMA::ptr()).ch(CH::id() as usize) }
+    }
+
+    // stops the dma and gets the i2c block out of the abort state, the frame is lost
+    fn abort(&mut self) -> bool {
+        let dma = unsafe { &*pac::DMA::ptr() };
+        dma.chan_abort().write(|w| unsafe { w.bits(1 << CH::id()) });
+        while dma.chan_abort().read().bits() & (1 << CH::id()) != 0 {}
+        compiler_fence(Ordering::Acquire);
+
+        let i2c = B::registers();
+        while i2c.ic_status().read().mst_activity().bit_is_set() {}
+        i2c.ic_clr_stop_det().read();
+        // reading this clears the abort source as well
+        i2c.ic_clr_tx_abrt().read();
+        false
+    }
+}
+
+impl<B: I2cBlock, P, CH: ChannelIndex> WriteOnlyDataCommand for I2cDma<B, P, CH>
+where
+    I2C<B, P>: I2c,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.wait_sent();
+        self.interface.send_commands(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.wait_sent();
+        self.interface.send_data(buf)
+    }
+}
+
+impl<B: I2cBlock, P, CH: ChannelIndex> Interface for I2cDma<B, P, CH>
+where
+    I2C<B, P>: I2c,
+{
+    fn can_queue(&self) -> bool {
+        true
+    }
+
+    fn queue(&mut self, bytes: &[u8], data: bool) {
+        // the dma might still be reading the queue
+        self.wait_sent();
+
+        let control = if data {
+            oled::I2C_DATA_BYTE
+        } else {
+            oled::I2C_COMMAND_BYTE
+        };
+        for byte in iter::once(control).chain(bytes.iter().copied()) {
+            self.queue[self.len] = byte as u16;
+            self.len += 1;
+        }
+        self.queue[self.len - 1] |= STOP;
+    }
+
+    fn send_queued(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+
+        // the address is still set from the blocking writes of the init sequence
+        let channel = Self::channel();
+        compiler_fence(Ordering::Release);
+        channel
+            .ch_read_addr()
+            .write(|w| unsafe { w.bits(self.queue.as_ptr() as u32) });
+        channel
+            .ch_write_addr()
+            .write(|w| unsafe { w.bits(B::registers().ic_data_cmd().as_ptr() as u32) });
+        channel
+            .ch_trans_count()
+            .write(|w| unsafe { w.bits(self.len as u32) });
+        channel.ch_ctrl_trig().write(|w| unsafe {
+            w.data_size()
+                .size_halfword()
+                .incr_read()
+                .set_bit()
+                .incr_write()
+                .clear_bit()
+                .treq_sel()
+                .bits(B::TX_DREQ)
+                .chain_to()
+                .bits(CH::id())
+                .en()
+                .set_bit()
+        });
+
+        self.len = 0;
+        self.busy = true;
+    }
+
+    fn wait_sent(&mut self) -> bool {
+        if !self.busy {
+            return true;
+        }
+        self.busy = false;
+
+        // a missing display aborts the transfer, the dma would wait for the fifo forever
+        let i2c = B::registers();
+        let aborted = || i2c.ic_tx_abrt_source().read().bits() != 0;
+        while Self::channel().ch_ctrl_trig().read().busy().bit_is_set() {
+            if aborted() {
+                return self.abort();
+            }
+        }
+        compiler_fence(Ordering::Acquire);
+
+        // the last bytes are still in the fifo when the dma is done
+        while i2c.ic_status().read().tfe().bit_is_clear()
+            || i2c.ic_status().read().mst_activity().bit_is_set()
+        {
+            if aborted() {
+                return self.abort();
+            }
+        }
+        // the blocking driver waits for its own stop condition
+        i2c.ic_clr_stop_det().read();
+
+        // nobody answered the last transfer
+        if aborted() {
+            return self.abort();
+        }
+        true
+    }
+}
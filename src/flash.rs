@@ -0,0 +1,104 @@
+use crate::board::hal::rom_data;
+use crate::board::FLASH_SIZE;
+use crate::replay::{Recording, RECORDING_SIZE};
+use core::cmp::Reverse;
+use core::{array, mem, ptr};
+
+// one recording per sector at the very end of flash, memory.x keeps the program out of there
+const SLOTS: usize = 4;
+const REPLAYS_OFFSET: u32 = FLASH_SIZE - (SLOTS * RECORDING_SIZE) as u32;
+// where flash shows up in the address space
+const XIP_BASE: usize = 0x1000_0000;
+const BOOT2_SIZE: usize = 256;
+
+// erase in 64K blocks where possible, the rom falls back to sectors
+const BLOCK_SIZE: u32 = 1 << 16;
+const BLOCK_ERASE: u8 = 0xd8;
+
+// the second stage bootloader, it sets up fast reads again after writing
+static mut BOOT2: [u32; BOOT2_SIZE / 4] = [0; BOOT2_SIZE / 4];
+
+extern "C" {
+    // where memory.x ends the program
+    static __replays_start: u8;
+}
+
+// a board profile and memory.x that disagree would have the replays overwrite the program
+pub fn check_layout() {
+    let end = ptr::addr_of!(__replays_start) as usize;
+    assert!(
+        end == XIP_BASE + REPLAYS_OFFSET as usize,
+        "memory.x doesn't match board::FLASH_SIZE"
+    );
+}
+
+const fn slot(index: usize) -> &'static Recording {
+    // flash is mapped read only, nothing else writes to it while we read
+    let address = XIP_BASE + REPLAYS_OFFSET as usize + index * RECORDING_SIZE;
+    unsafe { &*(address as *const Recording) }
+}
+
+// the recording `index` runs before the newest one
+pub fn recording(index: usize) -> Option<&'static Recording> {
+    let mut recordings: [_; SLOTS] =
+        array::from_fn(|index| Some(slot(index)).filter(|r| r.is_valid()));
+    recordings.sort_unstable_by_key(|r| Reverse(r.map(Recording::number)));
+    recordings.get(index).copied().flatten()
+}
+
+// everything that runs while flash is gone has to be looked up beforehand
+struct Rom {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+    boot2: unsafe extern "C" fn(),
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn write_sector(rom: &Rom, offset: u32, data: *const u8) {
+    (rom.connect_internal_flash)();
+    (rom.flash_exit_xip)();
+    (rom.flash_range_erase)(offset, RECORDING_SIZE, BLOCK_SIZE, BLOCK_ERASE);
+    (rom.flash_range_program)(offset, data, RECORDING_SIZE);
+    (rom.flash_flush_cache)();
+    (rom.boot2)();
+}
+
+// overwrites the oldest recording, takes a few frames, the other core must stay out of flash
+pub fn save(recording: &mut Recording) {
+    let newest = (0..SLOTS)
+        .map(slot)
+        .filter(|r| r.is_valid())
+        .map(Recording::number)
+        .max();
+    recording.set_number(newest.map_or(0, |number| number.wrapping_add(1)));
+
+    // empty slots go first
+    let index = (0..SLOTS)
+        .min_by_key(|&index| {
+            Some(slot(index))
+                .filter(|r| r.is_valid())
+                .map(Recording::number)
+        })
+        .unwrap_or(0);
+    let offset = REPLAYS_OFFSET + (index * RECORDING_SIZE) as u32;
+
+    cortex_m::interrupt::free(|_| unsafe {
+        let boot2 = ptr::addr_of_mut!(BOOT2);
+        ptr::copy_nonoverlapping(XIP_BASE as *const u32, boot2 as *mut u32, BOOT2_SIZE / 4);
+        // thumb code, the lowest bit has to be set
+        let entry = (boot2 as *const u8).add(1);
+        let rom = Rom {
+            connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+            flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+            flash_range_erase: rom_data::flash_range_erase::ptr(),
+            flash_range_program: rom_data::flash_range_program::ptr(),
+            flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+            boot2: mem::transmute::<*const u8, unsafe extern "C" fn()>(entry),
+        };
+        write_sector(&rom, offset, recording.as_bytes().as_ptr());
+    });
+}
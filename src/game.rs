@@ -1,79 +1,191 @@
-use crate::gfx;
-use core::fmt::Debug;
-use embedded_graphics::{draw_target::DrawTarget, pixelcolor::BinaryColor};
-use rand_core::RngCore;
+use crate::autopilot;
+use crate::gfx::viewport::{self, Layout, Viewport};
+use crate::gfx::{self, Palette};
+use crate::i10n;
+use crate::input::Button;
+use crate::replay::{Controls, Playback, Recorder, Recording};
+use crate::seed::{Random, Seed};
+use core::mem;
+use embedded_graphics::draw_target::DrawTarget;
 
+// how long a single tick lasts
+pub const TICK_MS: u32 = 50;
+// the start screen plays a demo run after a while without input
+const DEMO_AFTER_TICKS: u32 = 10_000 / TICK_MS;
+const DEMO_TICKS: u32 = 15_000 / TICK_MS;
+const REPLAY_MISMATCH_NOTICE_TICKS: u8 = 60;
+
+#[derive(Clone, Copy)]
 pub enum Screen {
     Start,
     Travel,
     Lock,
 }
 
-pub struct Game<R: RngCore> {
-    random: R,
+// things that happened during a tick, for sound and light effects
+#[derive(Clone, Copy)]
+pub enum Event {
+    PinSet,
+    PinSlipped,
+    LockOpened,
+    GoalReached,
+    Turn,
+    GameOver,
+}
+
+// several presses can land in the same tick, or several ticks of a fast replay
+const MAX_EVENTS: usize = 4;
+
+#[derive(Clone, Default)]
+pub struct Events {
+    events: [Option<Event>; MAX_EVENTS],
+    len: usize,
+}
+
+impl Events {
+    // anything beyond that in a single tick is dropped
+    pub fn push(&mut self, event: Event) {
+        if let Some(slot) = self.events.get_mut(self.len) {
+            *slot = Some(event);
+            self.len += 1;
+        }
+    }
+
+    // the oldest event first
+    pub fn pop(&mut self) -> Option<Event> {
+        let event = self.events.first_mut()?.take()?;
+        self.events.rotate_left(1);
+        self.len -= 1;
+        Some(event)
+    }
+}
+
+// everything that ends up on the screens, so another core can draw a copy of it
+#[derive(Clone)]
+pub struct View {
     screen: Screen,
     start: gfx::start::Start,
     travel: gfx::travel::TravelState,
     lock: gfx::lock::LockState,
+    battery: u8,
+    // a message covering the big screen, pausing the game
+    notice: Option<&'static str>,
+    replay: Option<Controls>,
+    demo: bool,
 }
 
-impl<R: RngCore> Game<R> {
-    pub fn new(mut random: R) -> Self {
-        let start = gfx::start::Start::new();
+pub struct Game {
+    // only picks the seed of each run, everything in a run comes from its seed
+    seeds: Random,
+    random: Random,
+    seed: Seed,
+    view: View,
+    events: Events,
+    notice_ticks: u8,
+    recorder: Recorder,
+    playback: Option<Playback>,
+    // which recording to play next, counting back from the newest
+    replay_request: Option<usize>,
+    // ticks on the start screen without any input
+    idle_ticks: u32,
+    // ticks left of the demo run
+    demo_ticks: Option<u32>,
+}
+
+impl Game {
+    pub fn new(entropy: u32) -> Self {
+        let mut seeds = Random::new(entropy);
+        let seed = Seed::random(&mut seeds);
+        let mut random = Random::from_seed(seed);
+        let start = gfx::start::Start::new(seed, None);
         let travel = gfx::travel::TravelState::new(&mut random);
         let lock = gfx::lock::LockState::new(0, &mut random);
 
         Self {
+            seeds,
             random,
-            start,
-            screen: Screen::Start,
-            travel,
-            lock,
+            seed,
+            view: View {
+                start,
+                screen: Screen::Start,
+                travel,
+                lock,
+                battery: 100,
+                notice: None,
+                replay: None,
+                demo: false,
+            },
+            events: Events::default(),
+            notice_ticks: 0,
+            recorder: Recorder::new(seed),
+            playback: None,
+            replay_request: None,
+            idle_ticks: 0,
+            demo_ticks: None,
         }
     }
 
+    pub const fn set_battery(&mut self, percent: u8) {
+        self.view.battery = percent;
+    }
+
+    pub const fn show_notice(&mut self, text: &'static str, ticks: u8) {
+        self.view.notice = Some(text);
+        self.notice_ticks = ticks;
+    }
+
+    pub const fn screen(&self) -> &Screen {
+        &self.view.screen
+    }
+
     // checks if the game state wants to transition to a different screen
     pub fn transition(&mut self) {
-        match self.screen {
+        match self.view.screen {
             Screen::Start => {
-                let Some(screen) = self.start.transition.take() else {
+                if mem::take(&mut self.view.start.replay) {
+                    self.replay_request = Some(0);
+                }
+                let Some(screen) = self.view.start.transition.take() else {
                     return;
                 };
 
-                // this is always travel state
-                self.travel = gfx::travel::TravelState::new(&mut self.random);
-                self.screen = screen;
+                // this is always travel state, everything from here on follows from the code
+                self.seed = self.view.start.seed();
+                self.start_run(self.seed);
+                self.recorder.start(self.seed);
+                self.view.screen = screen;
             }
             Screen::Travel => {
-                let Some(screen) = self.travel.transition.take() else {
+                let Some(screen) = self.view.travel.transition.take() else {
                     return;
                 };
 
                 match screen {
                     // game over
-                    Screen::Start => self.screen = screen,
+                    Screen::Start => self.game_over(),
                     // not possible
                     Screen::Travel => (),
                     // switch to lock mini game
                     Screen::Lock => {
-                        self.lock = gfx::lock::LockState::new(self.travel.score, &mut self.random);
-                        self.screen = screen;
+                        self.view.lock =
+                            gfx::lock::LockState::new(self.view.travel.score, &mut self.random);
+                        self.view.screen = screen;
                     }
                 }
             }
             Screen::Lock => {
-                let Some(screen) = self.lock.transition.take() else {
+                let Some(screen) = self.view.lock.transition.take() else {
                     return;
                 };
 
                 match screen {
                     // game over
-                    Screen::Start => self.screen = screen,
+                    Screen::Start => self.game_over(),
                     // switch to travel mini game
                     Screen::Travel => {
-                        self.travel.score = self.lock.score;
-                        self.travel.set_random_goal(&mut self.random);
-                        self.screen = screen;
+                        self.view.travel.score = self.view.lock.score;
+                        self.view.travel.set_random_goal(&mut self.random);
+                        self.view.screen = screen;
                     }
                     // not possible
                     Screen::Lock => (),
@@ -82,57 +194,507 @@ impl<R: RngCore> Game<R> {
         }
     }
 
+    // a replay has to start exactly like the run did
+    fn start_run(&mut self, seed: Seed) {
+        self.random = Random::from_seed(seed);
+        self.view.travel = gfx::travel::TravelState::new(&mut self.random);
+    }
+
+    // the start screen doubles as the game over screen, showing the code to share
+    fn game_over(&mut self) {
+        if self.playback.is_some() {
+            self.stop_replay();
+            return;
+        }
+        if self.stop_demo() {
+            return;
+        }
+        self.events.push(Event::GameOver);
+        self.finish_recording();
+        let next = Seed::random(&mut self.seeds);
+        self.view.start = gfx::start::Start::new(next, Some(self.seed));
+        self.view.screen = Screen::Start;
+    }
+
     pub fn tick(&mut self) {
-        match self.screen {
-            Screen::Start => self.start.tick(),
-            Screen::Travel => self.travel.tick(),
-            Screen::Lock => self.lock.tick(),
+        if self.view.notice.is_some() {
+            self.notice_ticks = self.notice_ticks.saturating_sub(1);
+            if self.notice_ticks == 0 {
+                self.view.notice = None;
+            }
+            return;
+        }
+
+        if self.playback.is_some() {
+            self.tick_replay();
+        } else {
+            if self.view.demo {
+                if let Some(button) = autopilot::press(&self.view) {
+                    self.press(button);
+                }
+            }
+            self.tick_screen();
+            self.recorder.tick();
+            self.tick_demo();
         }
     }
 
+    fn tick_demo(&mut self) {
+        match (&mut self.demo_ticks, self.view.screen) {
+            (Some(ticks), _) => {
+                *ticks = ticks.saturating_sub(1);
+                if *ticks == 0 {
+                    self.stop_demo();
+                }
+            }
+            (None, Screen::Start) => {
+                self.idle_ticks += 1;
+                if self.idle_ticks >= DEMO_AFTER_TICKS {
+                    self.start_demo();
+                }
+            }
+            (None, _) => (),
+        }
+    }
+
+    // a run with a fresh seed that nobody is going to see the code of
+    fn start_demo(&mut self) {
+        let seed = Seed::random(&mut self.seeds);
+        self.start_run(seed);
+        self.view.screen = Screen::Travel;
+        self.view.demo = true;
+        self.demo_ticks = Some(DEMO_TICKS);
+    }
+
+    // returns false if there was no demo running
+    pub const fn stop_demo(&mut self) -> bool {
+        if self.demo_ticks.take().is_none() {
+            return false;
+        }
+        self.idle_ticks = 0;
+        self.view.demo = false;
+        self.view.screen = Screen::Start;
+        true
+    }
+
+    pub const fn is_demo(&self) -> bool {
+        self.view.demo
+    }
+
+    fn tick_screen(&mut self) {
+        match self.view.screen {
+            Screen::Start => self.view.start.tick(),
+            Screen::Travel => self.view.travel.tick(),
+            Screen::Lock => self.view.lock.tick(),
+        }
+    }
+
+    // plays the recorded presses through the same code as live ones
+    fn tick_replay(&mut self) {
+        for _ in 0..self.view.replay.map_or(0, Controls::steps) {
+            while let Some(button) = self.playback.as_mut().and_then(Playback::press) {
+                self.press(button);
+            }
+            let Some(playback) = &mut self.playback else {
+                return;
+            };
+            if playback.is_done() {
+                // a replay that doesn't end like its run is a bug, but not worth crashing over
+                let mismatch = playback.score() != self.score();
+                self.stop_replay();
+                if mismatch {
+                    self.show_notice(i10n::REPLAY_MISMATCH, REPLAY_MISMATCH_NOTICE_TICKS);
+                }
+                return;
+            }
+            playback.tick();
+            self.tick_screen();
+            self.transition();
+        }
+    }
+
+    // returns the next event that happened since the last call
+    pub fn take_event(&mut self) -> Option<Event> {
+        self.events
+            .pop()
+            .or_else(|| self.view.travel.events.pop())
+            .or_else(|| self.view.lock.events.pop())
+    }
+
     pub fn button_action(&mut self) {
-        match self.screen {
-            Screen::Start => self.start.button_action(),
-            Screen::Travel => (),
-            Screen::Lock => self.lock.button_action(),
+        // dismiss the notice first
+        if self.view.notice.take().is_some() {
+            return;
+        }
+
+        match &mut self.view.replay {
+            Some(controls) => controls.toggle_pause(),
+            None => self.press(Button::Action),
         }
     }
 
     pub fn button_up(&mut self) {
-        match self.screen {
-            Screen::Start => (),
-            Screen::Travel => self.travel.button_up(),
-            Screen::Lock => (),
+        match &mut self.view.replay {
+            Some(controls) => controls.next_speed(),
+            None => self.press(Button::Up),
         }
     }
 
     pub fn button_down(&mut self) {
-        match self.screen {
-            Screen::Start => (),
-            Screen::Travel => self.travel.button_down(),
-            Screen::Lock => (),
+        // skip to the next older replay
+        match &self.playback {
+            Some(playback) => self.replay_request = Some(playback.index + 1),
+            None => self.press(Button::Down),
+        }
+    }
+
+    // a press that reaches the game, live or from a replay
+    fn press(&mut self, button: Button) {
+        self.idle_ticks = 0;
+        self.recorder.press(button, self.score());
+        match (self.view.screen, button) {
+            (Screen::Start, Button::Action) => self.view.start.button_action(),
+            (Screen::Start, Button::Up) => self.view.start.button_up(),
+            (Screen::Start, Button::Down) => self.view.start.button_down(),
+            (Screen::Travel, Button::Action) => (),
+            (Screen::Travel, Button::Up) => self.view.travel.button_up(),
+            (Screen::Travel, Button::Down) => self.view.travel.button_down(),
+            (Screen::Lock, Button::Action) => self.view.lock.button_action(),
+            (Screen::Lock, Button::Up | Button::Down) => (),
+        }
+    }
+
+    pub const fn score(&self) -> u32 {
+        match self.view.screen {
+            Screen::Lock => self.view.lock.score,
+            _ => self.view.travel.score,
+        }
+    }
+
+    // ends the recording of the current run early, e.g. before turning off
+    pub fn finish_recording(&mut self) {
+        self.recorder.finish(self.score());
+    }
+
+    // a finished recording that still has to be saved, handed out once
+    pub fn take_recording(&mut self) -> Option<&mut Recording> {
+        self.recorder.take_finished()
+    }
+
+    pub const fn take_replay_request(&mut self) -> Option<usize> {
+        self.replay_request.take()
+    }
+
+    pub fn play_replay(&mut self, recording: &'static Recording, index: usize) {
+        let playback = Playback::new(recording, index);
+        self.start_run(playback.seed());
+        self.view.screen = Screen::Travel;
+        self.view.replay = Some(Controls::new());
+        self.playback = Some(playback);
+    }
+
+    // back to the start screen the replays were picked from
+    pub const fn stop_replay(&mut self) {
+        if self.playback.take().is_some() {
+            self.view.replay = None;
+            self.view.screen = Screen::Start;
         }
     }
 
-    pub fn draw_big_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub const fn is_replay(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub const fn view(&self) -> &View {
+        &self.view
+    }
+}
+
+impl View {
+    pub const fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    pub const fn travel(&self) -> &gfx::travel::TravelState {
+        &self.travel
+    }
+
+    pub const fn lock(&self) -> &gfx::lock::LockState {
+        &self.lock
+    }
+
+    pub fn draw_big_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        if let Some(text) = self.notice {
+            return gfx::render_notice(display, text);
+        }
+
         match self.screen {
             Screen::Start => self.start.draw_big_screen(display),
             Screen::Travel => self.travel.draw_big_screen(display),
             Screen::Lock => self.lock.draw_big_screen(display),
+        }?;
+
+        // put the battery next to the taco counter
+        let width = gfx::width(display);
+        let right = match self.screen {
+            Screen::Start => width,
+            Screen::Travel => gfx::tacos_left(width, self.travel.score),
+            Screen::Lock => gfx::tacos_left(width, self.lock.score),
+        };
+        if let Some(controls) = self.replay {
+            gfx::render_replay(display, controls.steps())?;
+        }
+        if self.demo {
+            gfx::render_demo(display)?;
         }
+        gfx::render_battery(display, self.battery, right)
     }
 
-    pub fn draw_small_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub fn draw_small_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
         match self.screen {
             Screen::Start => self.start.draw_small_screen(display),
             Screen::Travel => self.travel.draw_small_screen(display),
             Screen::Lock => self.lock.draw_small_screen(display),
         }
     }
+
+    // both views on one display, for builds with a single screen
+    pub fn draw_single_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+        layout: Layout,
+    ) -> Result<(), D::Error> {
+        match layout {
+            Layout::Big => self.draw_big_screen(display),
+            Layout::Small => self.draw_small_screen(display),
+            Layout::Both => {
+                let (left, right) = viewport::halves(display.bounding_box().size);
+                self.draw_big_screen(&mut Viewport::new(display, left, viewport::HALF_SCALE))?;
+                self.draw_small_screen(&mut Viewport::new(display, right, viewport::HALF_SCALE))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::tests::Canvas;
+    use core::iter;
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use rand_core::RngCore;
+
+    #[test]
+    fn events_keep_their_order() {
+        let mut events = Events::default();
+        events.push(Event::PinSet);
+        events.push(Event::LockOpened);
+        assert!(matches!(events.pop(), Some(Event::PinSet)));
+        assert!(matches!(events.pop(), Some(Event::LockOpened)));
+        assert!(events.pop().is_none());
+    }
+
+    #[test]
+    fn events_drop_when_full() {
+        let mut events = Events::default();
+        for _ in 0..MAX_EVENTS {
+            events.push(Event::PinSet);
+        }
+        events.push(Event::GameOver);
+        assert_eq!(iter::from_fn(|| events.pop()).count(), MAX_EVENTS);
+    }
+
+    #[test]
+    fn presses_in_one_tick_keep_every_event() {
+        let mut game = Game::new(0);
+        game.view.screen = Screen::Lock;
+        // nowhere near the shear line, both presses slip
+        game.view.lock.pins.iter_mut().for_each(|pin| pin.state = 0);
+        game.press(Button::Action);
+        game.press(Button::Action);
+        let events = iter::from_fn(|| game.take_event()).count();
+        assert_eq!(events, 2);
+    }
+
+    fn step(game: &mut Game) {
+        game.tick();
+        game.transition();
+    }
+
+    // from the start screen into a run
+    fn start(game: &mut Game) {
+        // the start screen ignores presses for a moment
+        for _ in 0..3 {
+            step(game);
+        }
+        game.button_action();
+        step(game);
+        assert!(matches!(game.screen(), Screen::Travel));
+    }
+
+    // plays a run with random presses until it ends and hands out a copy of its recording,
+    // an endless run never breaks its last pick
+    fn record(entropy: u32, ticks: u32, every: u32, endless: bool) -> (&'static Recording, u32) {
+        let mut game = Game::new(entropy);
+        let mut presses = Random::new(entropy.wrapping_mul(7) + 1);
+        start(&mut game);
+
+        for tick in 0..ticks {
+            // mostly the autopilot so the runs get somewhere, with random presses in between
+            let button = if presses.next_u32().is_multiple_of(every) {
+                [Button::Action, Button::Up, Button::Down]
+                    .get(presses.next_u32() as usize % 3)
+                    .copied()
+            } else {
+                autopilot::press(game.view())
+            };
+            let lock = game.view().lock();
+            let breaks = matches!(game.screen(), Screen::Lock)
+                && lock.picks == 1
+                && !lock.active_pin().is_near_shear();
+            let button = button.filter(|&button| !(endless && breaks && button == Button::Action));
+            match button {
+                Some(Button::Action) => game.button_action(),
+                Some(Button::Up) => game.button_up(),
+                Some(Button::Down) => game.button_down(),
+                None => (),
+            }
+            // notices pause the run, the recording only sees the ticks that count
+            if tick % 997 == 500 {
+                game.show_notice("notice", 5);
+            }
+            step(&mut game);
+            if matches!(game.screen(), Screen::Start) {
+                break;
+            }
+        }
+
+        let score = game.score();
+        game.finish_recording();
+        let recording = game.take_recording().unwrap();
+        assert!(recording.is_valid());
+        // the copy outlives the game like flash does
+        let copy = Box::new(recording.clone());
+        assert!(game.take_recording().is_none());
+        (Box::leak(copy), score)
+    }
+
+    // returns the score and how many ticks the replay took
+    fn replay(recording: &'static Recording, fast: bool) -> (u32, u32) {
+        let mut game = Game::new(12345);
+        game.play_replay(recording, 0);
+        if fast {
+            game.button_up();
+            game.button_up();
+        }
+        let (mut score, mut ticks) = (0, 0);
+        while !matches!(game.screen(), Screen::Start) {
+            score = game.score();
+            step(&mut game);
+            ticks += 1;
+            assert!(ticks < 1_000_000);
+        }
+        (score, ticks)
+    }
+
+    #[test]
+    fn replays_end_with_the_recorded_score() {
+        let mut scored = 0;
+        for entropy in 0..200 {
+            let ticks = 200 + entropy * 37 % 3000;
+            let (recording, score) = record(entropy, ticks, 2 + entropy % 20, false);
+            assert_eq!(recording.score(), score);
+
+            let (replayed, slow_ticks) = replay(recording, false);
+            assert_eq!(replayed, score, "run {entropy}");
+            // the last tick runs a few replay ticks before it stops, tick_replay checks the score
+            let (_, fast_ticks) = replay(recording, true);
+            assert!(fast_ticks <= slow_ticks / 4 + 2);
+
+            if score > 0 {
+                scored += 1;
+            }
+        }
+        // make sure the runs get anywhere
+        assert!(scored > 50, "{scored}");
+    }
+
+    fn draw_big(view: &View) -> Vec<BinaryColor> {
+        let mut canvas = Canvas::new(128, 64);
+        view.draw_big_screen(&mut canvas).unwrap();
+        canvas.pixels
+    }
+
+    #[test]
+    fn broken_picks_end_the_run_with_its_code() {
+        let mut game = Game::new(5);
+        start(&mut game);
+        let seed = game.seed;
+
+        // ride to the first lock, then press at all the wrong moments
+        let mut ticks = 0;
+        while !matches!(game.screen(), Screen::Start) {
+            match game.screen() {
+                Screen::Lock if !game.view().lock().active_pin().is_near_shear() => {
+                    game.button_action();
+                }
+                Screen::Lock => (),
+                _ => match autopilot::press(game.view()) {
+                    Some(Button::Up) => game.button_up(),
+                    Some(Button::Down) => game.button_down(),
+                    _ => (),
+                },
+            }
+            step(&mut game);
+            ticks += 1;
+            assert!(ticks < 10_000);
+        }
+
+        assert!(iter::from_fn(|| game.take_event()).any(|event| matches!(event, Event::GameOver)));
+        assert!(game.take_recording().unwrap().seed() == seed);
+
+        // the start screen shows the code of the run that just ended
+        let next = game.view.start.seed();
+        assert!(next != seed);
+        let mut expected = game.view.clone();
+        expected.start = gfx::start::Start::new(next, Some(seed));
+        assert!(draw_big(game.view()) == draw_big(&expected));
+        expected.start = gfx::start::Start::new(next, None);
+        assert!(draw_big(game.view()) != draw_big(&expected));
+    }
+
+    #[test]
+    fn random_input_keeps_the_game_consistent() {
+        for entropy in 0..100 {
+            let mut game = Game::new(entropy);
+            let mut presses = Random::new(!entropy);
+            for _ in 0..20_000 {
+                match presses.next_u32() % 8 {
+                    0 => game.button_action(),
+                    1 => game.button_up(),
+                    2 => game.button_down(),
+                    _ => step(&mut game),
+                }
+                gfx::lock::tests::check(game.view().lock());
+                // the travel state stays where the bike reached the goal while it's picking the lock
+                if matches!(game.screen(), Screen::Travel) {
+                    gfx::travel::tests::check(game.view().travel());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn full_recordings_replay_up_to_where_they_stop() {
+        // a press every tick fills the recording long before the run ends
+        let (recording, _) = record(3, 20_000, 1, true);
+        let (replayed, _) = replay(recording, false);
+        assert_eq!(replayed, recording.score());
+    }
 }
@@ -0,0 +1,126 @@
+use crate::game::TICK_MS;
+use embedded_graphics::{draw_target::DrawTarget, prelude::*, primitives::Rectangle};
+
+// move everything by a pixel every two minutes, around where it belongs so no edge gets cut off
+// by more than a pixel
+const DRIFT_TICKS: u16 = (120 * 1000 / TICK_MS) as u16;
+const DRIFT_PATH: [Point; 8] = [
+    Point::new(0, 0),
+    Point::new(1, 0),
+    Point::new(1, 1),
+    Point::new(0, 1),
+    Point::new(-1, 1),
+    Point::new(-1, 0),
+    Point::new(-1, -1),
+    Point::new(0, -1),
+];
+
+#[derive(Clone, Copy)]
+pub struct Drift {
+    ticks: u16,
+    step: u8,
+}
+
+impl Drift {
+    pub const fn new() -> Self {
+        Self {
+            ticks: DRIFT_TICKS,
+            step: 0,
+        }
+    }
+
+    pub const fn tick(&mut self) {
+        self.ticks = self.ticks.saturating_sub(1);
+        if self.ticks == 0 {
+            self.ticks = DRIFT_TICKS;
+            self.step = (self.step + 1) % DRIFT_PATH.len() as u8;
+        }
+    }
+
+    pub const fn offset(&self) -> Point {
+        DRIFT_PATH[self.step as usize]
+    }
+
+    pub const fn apply<'a, D: DrawTarget>(&self, target: &'a mut D) -> Shifted<'a, D> {
+        Shifted {
+            target,
+            offset: self.offset(),
+        }
+    }
+}
+
+impl Default for Drift {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// draws everything moved by an offset, dropping whatever ends up outside of the display
+pub struct Shifted<'a, D> {
+    target: &'a mut D,
+    offset: Point,
+}
+
+impl<D: DrawTarget> Dimensions for Shifted<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Shifted<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.target.bounding_box();
+        let offset = self.offset;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point + offset, color))
+                .filter(|Pixel(point, _)| area.contains(*point)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area
+            .translate(self.offset)
+            .intersection(&self.target.bounding_box());
+        self.target.fill_solid(&area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_stays_around_the_origin() {
+        let mut previous = DRIFT_PATH[DRIFT_PATH.len() - 1];
+        for point in DRIFT_PATH {
+            assert!(point.x.abs() <= 1 && point.y.abs() <= 1);
+            // a single pixel at a time, also when starting over
+            let step = point - previous;
+            assert!(step.x.abs() <= 1 && step.y.abs() <= 1);
+            previous = point;
+        }
+    }
+
+    #[test]
+    fn moves_after_two_minutes() {
+        let mut drift = Drift::new();
+        for _ in 1..DRIFT_TICKS {
+            drift.tick();
+        }
+        assert_eq!(drift.offset(), Point::zero());
+        drift.tick();
+        assert_eq!(drift.offset(), Point::new(1, 0));
+    }
+}
@@ -1,12 +1,11 @@
-use crate::game::Screen;
-use crate::gfx;
+use crate::game::{Event, Events, Screen};
+use crate::gfx::sprite::{Animation, Clip, Frame, Playback};
+use crate::gfx::{self, Palette};
 use core::cmp;
-use core::fmt::Debug;
 use embedded_graphics::{
     draw_target::DrawTarget,
-    pixelcolor::BinaryColor,
     prelude::*,
-    primitives::{Circle, Line, Rectangle, RoundedRectangle},
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle, RoundedRectangle},
 };
 use rand::Rng;
 use rand_core::RngCore;
@@ -17,16 +16,42 @@ const KEYHOLE_WIDTH: u32 = 10;
 const KEYHOLE_HEIGHT: u32 = 26;
 const KEYHOLE_OPEN_TWIST: i32 = 5;
 
+static KEYHOLE_CLOSED: Clip<i32> = Clip {
+    frames: &[Frame { value: 0, ticks: 1 }],
+    playback: Playback::Once,
+};
+// overshoot a little before settling, like a spring
+static KEYHOLE_OPEN: Clip<i32> = Clip {
+    frames: &[
+        Frame {
+            value: KEYHOLE_OPEN_TWIST + 3,
+            ticks: 1,
+        },
+        Frame {
+            value: KEYHOLE_OPEN_TWIST - 2,
+            ticks: 1,
+        },
+        Frame {
+            value: KEYHOLE_OPEN_TWIST + 1,
+            ticks: 1,
+        },
+        Frame {
+            value: KEYHOLE_OPEN_TWIST,
+            ticks: 1,
+        },
+    ],
+    playback: Playback::Once,
+};
+
 const TENSION_TOOL_LENGTH: i32 = 25;
 const TENSION_TOOL_KEYHOLE_OFFSET: i32 = 5;
 const TENSION_TOOL_WIDTH: u32 = 4;
 
-const KEYHOLE_Y_OFFSET: i32 = 12;
-
 // big screen consts
 const LOCK_LENGTH: u32 = 95;
 const LOCK_HEIGHT: u32 = 40;
-const LOCK_X_OFFSET: i32 = 25;
+// the pick comes in from the left, so keep the lock on the right
+const LOCK_RIGHT_OFFSET: i32 = 8;
 const SIDE_LOCK_Y_OFFSET: i32 = 8;
 const LOCK_ROUND_CORNERS: u32 = 6;
 
@@ -47,6 +72,11 @@ const PICK_WIDTH: u32 = 2;
 const PICK_Y_OFFSET: u32 = 3;
 const PICK_HOOK_HEIGHT: u32 = 3;
 
+// the spare picks, bottom left where the pick doesn't reach
+const SPARE_PICK_LENGTH: u32 = 12;
+const SPARE_PICK_SPACING: i32 = 3;
+const SPARE_PICK_BOTTOM_OFFSET: i32 = 3;
+
 // game constants
 const MIN_CHALLENGE_SIZE: u32 = 5;
 const MAX_CHALLENGE_SIZE: u32 = PIN_HEIGHT - SHEAR_LINE_DISTANCE - 4;
@@ -58,20 +88,25 @@ const MIN_SCORE_REWARD: u32 = 100;
 const MAX_SCORE_REWARD: u32 = 250;
 
 const SOLVE_COOLDOWN: u8 = 5;
+// slips a lock takes before the pick breaks and the run is over
+const PICKS: u8 = 3;
 
-// big screen absolute positions
-const LOCK_TOP_OFFSET: i32 =
-    gfx::centered(gfx::DISPLAY_HEIGHT - SIDE_LOCK_Y_OFFSET, LOCK_HEIGHT) + SIDE_LOCK_Y_OFFSET;
-const KEYWAY_TOP_OFFSET: i32 = LOCK_TOP_OFFSET + KEYWAY_Y_OFFSET as i32;
-const SHEAR_LINE_TOP_OFFSET: i32 = KEYWAY_TOP_OFFSET - SHEAR_LINE_DISTANCE as i32;
-const PINS_TOP_OFFSET: i32 = LOCK_TOP_OFFSET + PINS_Y_OFFSET as i32;
-const PINS_LEFT_OFFSET: i32 = LOCK_X_OFFSET + PINS_X_OFFSET as i32;
+// big screen positions, relative to the size of the screen
+const fn lock_left_offset(width: i32) -> i32 {
+    width - LOCK_LENGTH as i32 - LOCK_RIGHT_OFFSET
+}
+
+const fn lock_top_offset(height: i32) -> i32 {
+    gfx::centered(height - SIDE_LOCK_Y_OFFSET, LOCK_HEIGHT) + SIDE_LOCK_Y_OFFSET
+}
 
+#[derive(Clone, Copy)]
 pub enum Direction {
     Up,
     Down,
 }
 
+#[derive(Clone)]
 pub struct LockPin {
     pub state: u8,
     pub height: u8,
@@ -107,6 +142,7 @@ impl LockPin {
     }
 }
 
+#[derive(Clone)]
 pub struct LockState {
     pub open: bool,
     pub score: u32,
@@ -114,6 +150,9 @@ pub struct LockState {
     pub pins: [LockPin; NUM_PINS],
     pub current_pin: u8,
     pub solve_cooldown: u8,
+    pub picks: u8,
+    keyhole: Animation<i32>,
+    pub events: Events,
     pub transition: Option<Screen>,
 }
 
@@ -132,16 +171,25 @@ impl LockState {
             ],
             current_pin: (NUM_PINS - 1) as u8,
             solve_cooldown: SOLVE_COOLDOWN,
+            picks: PICKS,
+            keyhole: Animation::new(&KEYHOLE_CLOSED),
+            events: Events::default(),
             transition: None,
         }
     }
 
+    pub fn active_pin(&self) -> &LockPin {
+        &self.pins[cmp::min(self.current_pin as usize, NUM_PINS - 1)]
+    }
+
     fn current_pin(&mut self) -> &mut LockPin {
         self.current_pin = cmp::min(self.current_pin, NUM_PINS as u8 - 1);
         &mut self.pins[self.current_pin as usize]
     }
 
     pub fn tick(&mut self) {
+        self.keyhole.tick();
+
         if self.open {
             self.solve_cooldown = self.solve_cooldown.saturating_sub(1);
             if self.solve_cooldown == 0 {
@@ -166,159 +214,230 @@ impl LockState {
     }
 
     pub fn button_action(&mut self) {
-        if self.open == true {
+        if self.open || self.picks == 0 {
             return;
         }
 
         let pin = self.current_pin();
         if !pin.is_near_shear() {
-            self.current_pin += 1;
+            // the pin set before drops again, if there is one
+            self.current_pin = cmp::min(self.current_pin + 1, NUM_PINS as u8 - 1);
+            self.events.push(Event::PinSlipped);
+            self.picks -= 1;
+            if self.picks == 0 {
+                self.transition = Some(Screen::Start);
+            }
             return;
         }
 
         if self.current_pin == 0 {
             self.score += self.reward;
             self.open = true;
+            self.keyhole.play(&KEYHOLE_OPEN);
+            self.events.push(Event::LockOpened);
         } else {
             self.current_pin = self.current_pin.saturating_sub(1);
+            self.events.push(Event::PinSet);
         }
     }
 
-    pub fn draw_big_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub fn draw_big_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let lock_left = lock_left_offset(gfx::width(display));
+        let lock_top = lock_top_offset(gfx::height(display));
+        let keyway_top = lock_top + KEYWAY_Y_OFFSET as i32;
+        let shear_line_top = keyway_top - SHEAR_LINE_DISTANCE as i32;
+        let pins_top = lock_top + PINS_Y_OFFSET as i32;
+        let pins_left = lock_left + PINS_X_OFFSET as i32;
+
         // render lock
         RoundedRectangle::with_equal_corners(
             Rectangle::new(
-                Point::new(LOCK_X_OFFSET, LOCK_TOP_OFFSET),
+                Point::new(lock_left, lock_top),
                 Size::new(LOCK_LENGTH, LOCK_HEIGHT),
             ),
             Size::new(LOCK_ROUND_CORNERS, LOCK_ROUND_CORNERS),
         )
-        .into_styled(gfx::WHITE)
-        .draw(display)
-        .unwrap();
+        .into_styled(gfx::white())
+        .draw(display)?;
 
         // render keyway
         Rectangle::new(
-            Point::new(LOCK_X_OFFSET, KEYWAY_TOP_OFFSET),
+            Point::new(lock_left, keyway_top),
             Size::new(KEYWAY_LENGTH, KEYWAY_HEIGHT),
         )
-        .into_styled(gfx::BLACK)
-        .draw(display)
-        .unwrap();
+        .into_styled(gfx::black())
+        .draw(display)?;
 
         // render shear line
         Rectangle::new(
-            Point::new(LOCK_X_OFFSET, SHEAR_LINE_TOP_OFFSET),
+            Point::new(lock_left, shear_line_top),
             Size::new(KEYWAY_LENGTH, 1),
         )
-        .into_styled(gfx::BLACK)
-        .draw(display)
-        .unwrap();
+        .into_styled(gfx::black())
+        .draw(display)?;
 
         // render pins
         for (num, pin) in self.pins.iter().enumerate() {
             let num = num as i32;
 
             let point = Point::new(
-                PINS_LEFT_OFFSET + (num * (PIN_WIDTH + PIN_X_SPACING) as i32),
-                PINS_TOP_OFFSET,
+                pins_left + (num * (PIN_WIDTH + PIN_X_SPACING) as i32),
+                pins_top,
             );
             Rectangle::new(point, Size::new(PIN_WIDTH, PIN_HEIGHT))
-                .into_styled(gfx::BLACK)
-                .draw(display)
-                .unwrap();
-
-            Rectangle::new(
-                point + Point::new(1, 1 + pin.state as i32),
-                Size::new(PIN_WIDTH - 2, pin.height as u32),
-            )
-            .into_styled(gfx::WHITE)
-            .draw(display)
-            .unwrap();
+                .into_styled(gfx::black())
+                .draw(display)?;
+
+            // lit from the left
+            let pin_point = point + Point::new(1, 1 + pin.state as i32);
+            Rectangle::new(pin_point, Size::new(PIN_WIDTH - 2, pin.height as u32))
+                .into_styled(PrimitiveStyle::with_fill(C::SHADE))
+                .draw(display)?;
+            Rectangle::new(pin_point, Size::new(PIN_WIDTH / 2 - 1, pin.height as u32))
+                .into_styled(gfx::white())
+                .draw(display)?;
         }
 
         // render pick
-        let pick_length = LOCK_X_OFFSET as u32
-            + PINS_X_OFFSET
-            + (self.current_pin as u32 * (PIN_WIDTH + PIN_X_SPACING))
-            + (PIN_WIDTH / 2)
-            + (PICK_WIDTH / 2);
+        // the lock may hang off the left edge on narrow screens
+        let pick_length = cmp::max(
+            lock_left
+                + (PINS_X_OFFSET
+                    + (self.current_pin as u32 * (PIN_WIDTH + PIN_X_SPACING))
+                    + (PIN_WIDTH / 2)
+                    + (PICK_WIDTH / 2)) as i32,
+            PICK_WIDTH as i32,
+        ) as u32;
         Rectangle::new(
-            Point::new(
-                0,
-                KEYWAY_TOP_OFFSET + (PICK_Y_OFFSET + PICK_HOOK_HEIGHT) as i32,
-            ),
+            Point::new(0, keyway_top + (PICK_Y_OFFSET + PICK_HOOK_HEIGHT) as i32),
             Size::new(pick_length, PICK_WIDTH),
         )
-        .into_styled(gfx::WHITE)
-        .draw(display)
-        .unwrap();
+        .into_styled(gfx::white())
+        .draw(display)?;
 
         // render pick hook
         Rectangle::new(
             Point::new(
                 (pick_length - PICK_WIDTH) as i32,
-                KEYWAY_TOP_OFFSET + PICK_Y_OFFSET as i32,
+                keyway_top + PICK_Y_OFFSET as i32,
             ),
             Size::new(PICK_WIDTH, PICK_HOOK_HEIGHT),
         )
-        .into_styled(gfx::WHITE)
-        .draw(display)
-        .unwrap();
+        .into_styled(gfx::white())
+        .draw(display)?;
+
+        // render the picks left, the one in the lock is the last
+        let spare_top = gfx::height(display) - SPARE_PICK_BOTTOM_OFFSET;
+        for num in 1..self.picks as i32 {
+            let left = (num - 1) * (SPARE_PICK_LENGTH as i32 + SPARE_PICK_SPACING);
+            Rectangle::new(
+                Point::new(left, spare_top),
+                Size::new(SPARE_PICK_LENGTH, PICK_WIDTH),
+            )
+            .into_styled(gfx::white())
+            .draw(display)?;
+        }
 
         // render score
-        gfx::render_tacos(display, self.score);
+        gfx::render_tacos(display, self.score)
     }
 
-    pub fn draw_small_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
-        let twist = self.open.then_some(KEYHOLE_OPEN_TWIST).unwrap_or_default();
+    pub fn draw_small_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let twist = self.keyhole.current();
+        let (width, height) = (gfx::width(display), gfx::height(display));
+        let circle_top = gfx::centered(height, CIRLCE_DIAMETER);
 
         // circle
-        Circle::new(Point::new(44, KEYHOLE_Y_OFFSET), 40)
-            .into_styled(gfx::WHITE)
-            .draw(display)
-            .unwrap();
+        Circle::new(
+            Point::new(gfx::centered(width, CIRLCE_DIAMETER), circle_top),
+            CIRLCE_DIAMETER,
+        )
+        .into_styled(gfx::white())
+        .draw(display)?;
 
         // keyhole
         Line::new(
             Point::new(
-                gfx::line_tweak(gfx::centered(gfx::DISPLAY_WIDTH, 0)) + twist,
-                gfx::centered(CIRLCE_DIAMETER as i32, KEYHOLE_HEIGHT) + KEYHOLE_Y_OFFSET,
+                gfx::line_tweak(gfx::centered(width, 0)) + twist,
+                gfx::centered(CIRLCE_DIAMETER as i32, KEYHOLE_HEIGHT) + circle_top,
             ),
             Point::new(
-                gfx::line_tweak(gfx::centered(gfx::DISPLAY_WIDTH, 0)) - twist,
+                gfx::line_tweak(gfx::centered(width, 0)) - twist,
                 gfx::line_tweak(
                     gfx::centered(CIRLCE_DIAMETER as i32, KEYHOLE_HEIGHT)
-                        + KEYHOLE_Y_OFFSET
+                        + circle_top
                         + KEYHOLE_HEIGHT as i32,
                 ),
             ),
         )
         .into_styled(gfx::black_stroke(KEYHOLE_WIDTH))
-        .draw(display)
-        .unwrap();
+        .draw(display)?;
 
         // pick
         Line::new(
             Point::new(
-                gfx::centered(gfx::DISPLAY_WIDTH, 0) - twist,
-                gfx::centered(gfx::DISPLAY_HEIGHT, 0) + TENSION_TOOL_KEYHOLE_OFFSET,
+                gfx::centered(width, 0) - twist,
+                gfx::centered(height, 0) + TENSION_TOOL_KEYHOLE_OFFSET,
             ),
             Point::new(
-                gfx::centered(gfx::DISPLAY_WIDTH, 0) + TENSION_TOOL_LENGTH - (twist * 5),
-                gfx::centered(gfx::DISPLAY_HEIGHT, 0)
-                    + TENSION_TOOL_LENGTH
-                    + TENSION_TOOL_KEYHOLE_OFFSET,
+                gfx::centered(width, 0) + TENSION_TOOL_LENGTH - (twist * 5),
+                gfx::centered(height, 0) + TENSION_TOOL_LENGTH + TENSION_TOOL_KEYHOLE_OFFSET,
             ),
         )
         .into_styled(gfx::white_stroke(TENSION_TOOL_WIDTH))
-        .draw(display)
-        .unwrap();
+        .draw(display)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::seed::Random;
+
+    // holds after every tick and press, whatever they were
+    pub fn check(state: &LockState) {
+        assert!((state.current_pin as usize) < NUM_PINS);
+        assert!(state.picks <= PICKS);
+        for pin in &state.pins {
+            assert!((pin.state + pin.height) as u32 <= PIN_HEIGHT);
+        }
+    }
+
+    #[test]
+    fn random_presses_keep_the_pins_in_the_lock() {
+        for entropy in 0..1000 {
+            let mut random = Random::new(entropy);
+            let mut state = LockState::new(0, &mut random);
+            for _ in 0..2000 {
+                if random.next_u32().is_multiple_of(3) {
+                    state.button_action();
+                } else {
+                    state.tick();
+                }
+                check(&state);
+            }
+        }
+    }
+
+    #[test]
+    fn the_pick_breaks_after_a_few_slips() {
+        let mut state = LockState::new(0, Random::new(1));
+        // nowhere near the shear line, every press slips
+        for _ in 0..PICKS {
+            assert!(state.transition.is_none());
+            state.pins.iter_mut().for_each(|pin| pin.state = 0);
+            state.button_action();
+        }
+        assert_eq!(state.picks, 0);
+        assert!(matches!(state.transition, Some(Screen::Start)));
+        assert!(!state.open);
     }
 }
@@ -1,34 +1,56 @@
+pub mod burnin;
 pub mod lock;
+pub mod palette;
+pub mod sprite;
 pub mod start;
+pub mod text;
 pub mod travel;
+pub mod viewport;
 
-use core::fmt::Debug;
+use crate::i10n;
+use core::{cmp, str};
 use embedded_graphics::{
-    mono_font::{ascii, MonoFont, MonoTextStyle, MonoTextStyleBuilder},
-    pixelcolor::BinaryColor,
+    mono_font::{ascii, MonoFont},
     prelude::*,
-    primitives::PrimitiveStyle,
+    primitives::{PrimitiveStyle, Rectangle},
     text::{Baseline, Text},
 };
+pub use palette::Palette;
+pub use text::TextStyle;
 
+// the size of the panels we drive, scenes lay themselves out with `width` and `height`
 pub const DISPLAY_WIDTH: i32 = 128;
 pub const DISPLAY_HEIGHT: i32 = 64;
 
-const BLACK: PrimitiveStyle<BinaryColor> = PrimitiveStyle::with_fill(BinaryColor::Off);
-const WHITE: PrimitiveStyle<BinaryColor> = PrimitiveStyle::with_fill(BinaryColor::On);
-
 pub const CHAR_WIDTH: usize = 4;
-pub const TEXT_STYLE: MonoTextStyle<BinaryColor> = MonoTextStyleBuilder::new()
-    .font(&ascii::FONT_4X6)
-    .text_color(BinaryColor::On)
-    .build();
+pub const TEXT_FONT: &MonoFont = &ascii::FONT_4X6;
+
+pub const fn text_style<C: Palette>() -> TextStyle<C> {
+    TextStyle::new(TEXT_FONT)
+}
+
+pub fn width<D: Dimensions>(display: &D) -> i32 {
+    display.bounding_box().size.width as i32
+}
+
+pub fn height<D: Dimensions>(display: &D) -> i32 {
+    display.bounding_box().size.height as i32
+}
+
+pub const fn black<C: Palette>() -> PrimitiveStyle<C> {
+    PrimitiveStyle::with_fill(C::BACKGROUND)
+}
+
+pub const fn white<C: Palette>() -> PrimitiveStyle<C> {
+    PrimitiveStyle::with_fill(C::FOREGROUND)
+}
 
-pub const fn black_stroke(width: u32) -> PrimitiveStyle<BinaryColor> {
-    PrimitiveStyle::with_stroke(BinaryColor::Off, width)
+pub const fn black_stroke<C: Palette>(width: u32) -> PrimitiveStyle<C> {
+    PrimitiveStyle::with_stroke(C::BACKGROUND, width)
 }
 
-pub const fn white_stroke(width: u32) -> PrimitiveStyle<BinaryColor> {
-    PrimitiveStyle::with_stroke(BinaryColor::On, width)
+pub const fn white_stroke<C: Palette>(width: u32) -> PrimitiveStyle<C> {
+    PrimitiveStyle::with_stroke(C::FOREGROUND, width)
 }
 
 pub const fn centered(outer: i32, inner: u32) -> i32 {
@@ -41,42 +63,297 @@ pub const fn line_tweak(num: i32) -> i32 {
     num - 1
 }
 
-pub const fn text_align_right(text: &str, total: u8) -> i32 {
-    (total as usize - (text.len() * CHAR_WIDTH)) as i32
+pub const fn text_align_right(text: &str, total: i32) -> i32 {
+    total - (text.len() * CHAR_WIDTH) as i32
 }
 
 pub const fn text_align_center(text: &str, total: i32, font: &MonoFont) -> i32 {
     centered(total, text.len() as u32 * font.character_size.width)
 }
 
-pub fn render_tacos<D: DrawTarget<Color = BinaryColor>>(display: &mut D, score: u32)
-where
-    <D as DrawTarget>::Error: Debug,
-{
-    let style = TEXT_STYLE;
+const TACOS_UNIT: &str = " tacos";
+
+// next to the direction, clear of the taco counter
+const STATUS_LEFT: i32 = 24;
+
+const BATTERY_WIDTH: u32 = 8;
+const BATTERY_HEIGHT: u32 = 5;
+const BATTERY_GAP: i32 = 2;
+
+// where the taco counter starts, so we can draw things next to it
+pub fn tacos_left(width: i32, score: u32) -> i32 {
+    let mut buf = itoa::Buffer::new();
+    let digits = buf.format(score).len();
+    width - ((TACOS_UNIT.len() + digits) * CHAR_WIDTH) as i32
+}
+
+// a small battery icon, right aligned to `right`
+pub fn render_battery<C: Palette, D: DrawTarget<Color = C>>(
+    display: &mut D,
+    percent: u8,
+    right: i32,
+) -> Result<(), D::Error> {
+    let left = right - BATTERY_GAP - BATTERY_WIDTH as i32 - 1;
+
+    // body
+    Rectangle::new(
+        Point::new(left, 0),
+        Size::new(BATTERY_WIDTH, BATTERY_HEIGHT),
+    )
+    .into_styled(white_stroke(1))
+    .draw(display)?;
+
+    // tip
+    Rectangle::new(
+        Point::new(left + BATTERY_WIDTH as i32, 1),
+        Size::new(1, BATTERY_HEIGHT - 2),
+    )
+    .into_styled(white())
+    .draw(display)?;
+
+    // charge
+    let charge = (BATTERY_WIDTH - 2) * cmp::min(percent, 100) as u32 / 100;
+    Rectangle::new(
+        Point::new(left + 1, 1),
+        Size::new(charge, BATTERY_HEIGHT - 2),
+    )
+    .into_styled(white())
+    .draw(display)?;
+
+    Ok(())
+}
+
+// a single line of big text in the middle of the screen
+pub fn render_notice<C: Palette, D: DrawTarget<Color = C>>(
+    display: &mut D,
+    text: &str,
+) -> Result<(), D::Error> {
+    let (width, height) = (width(display), height(display));
+
+    // fall back to the small font for long messages
+    let style = if text.len() as i32 * start::BIG_FONT.character_size.width as i32 <= width {
+        start::big_text()
+    } else {
+        text_style()
+    };
+    Text::with_baseline(
+        text,
+        Point::new(
+            text_align_center(text, width, style.font),
+            centered(height, style.font.character_size.height),
+        ),
+        style,
+        Baseline::Top,
+    )
+    .draw(display)?;
+
+    Ok(())
+}
+
+// what a replay is doing, zero speed is paused
+pub fn render_replay<C: Palette, D: DrawTarget<Color = C>>(
+    display: &mut D,
+    speed: u8,
+) -> Result<(), D::Error> {
+    let digits = [b' ', b'0' + speed, b'x'];
+    let (text, suffix) = if speed == 0 {
+        (i10n::PAUSED, "")
+    } else {
+        (i10n::REPLAY, str::from_utf8(&digits).unwrap_or(""))
+    };
+    Text::with_baseline(
+        text,
+        Point::new(STATUS_LEFT, 0),
+        text_style(),
+        Baseline::Top,
+    )
+    .draw(display)?;
+    let left = STATUS_LEFT + (text.len() * CHAR_WIDTH) as i32;
+    Text::with_baseline(suffix, Point::new(left, 0), text_style(), Baseline::Top).draw(display)?;
+
+    Ok(())
+}
+
+pub fn render_demo<C: Palette, D: DrawTarget<Color = C>>(display: &mut D) -> Result<(), D::Error> {
+    Text::with_baseline(
+        i10n::DEMO,
+        Point::new(STATUS_LEFT, 0),
+        text_style(),
+        Baseline::Top,
+    )
+    .draw(display)?;
+
+    Ok(())
+}
+
+pub fn render_tacos<C: Palette, D: DrawTarget<Color = C>>(
+    display: &mut D,
+    score: u32,
+) -> Result<(), D::Error> {
+    let style = text_style();
+    let width = width(display);
 
     // unit
-    let tacos = " tacos";
+    let tacos = TACOS_UNIT;
     Text::with_baseline(
         tacos,
-        Point::new(text_align_right(tacos, DISPLAY_WIDTH as u8), 0),
+        Point::new(text_align_right(tacos, width), 0),
         style,
         Baseline::Top,
     )
-    .draw(display)
-    .unwrap();
+    .draw(display)?;
     let unit_width = tacos.len() as u32 * style.font.character_size.width;
-    let remaining_width = DISPLAY_WIDTH - unit_width as i32;
+    let remaining_width = width - unit_width as i32;
 
     // score
     let mut buf = itoa::Buffer::new();
     let buf = buf.format(score);
     Text::with_baseline(
         buf,
-        Point::new(text_align_right(buf, remaining_width as u8), 0),
+        Point::new(text_align_right(buf, remaining_width), 0),
         style,
         Baseline::Top,
     )
-    .draw(display)
-    .unwrap();
+    .draw(display)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::gfx::lock::LockState;
+    use crate::gfx::start::Start;
+    use crate::gfx::travel::TravelState;
+    use crate::gfx::viewport::{self, Viewport};
+    use crate::seed::{Random, Seed};
+    use core::convert::Infallible;
+    use embedded_graphics::pixelcolor::{BinaryColor, Gray4};
+
+    // a display of any size that remembers what landed outside of it
+    pub struct Canvas<C> {
+        pub size: Size,
+        pub pixels: Vec<C>,
+        pub outside: usize,
+    }
+
+    impl<C: Palette> Canvas<C> {
+        pub fn new(width: u32, height: u32) -> Self {
+            Self {
+                size: Size::new(width, height),
+                pixels: vec![C::BACKGROUND; (width * height) as usize],
+                outside: 0,
+            }
+        }
+
+        pub fn lit(&self) -> usize {
+            self.pixels.iter().filter(|&&c| c != C::BACKGROUND).count()
+        }
+    }
+
+    impl<C> Dimensions for Canvas<C> {
+        fn bounding_box(&self) -> Rectangle {
+            Rectangle::new(Point::zero(), self.size)
+        }
+    }
+
+    impl<C: PixelColor> DrawTarget for Canvas<C> {
+        type Color = C;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if self.bounding_box().contains(point) {
+                    let index = point.y as u32 * self.size.width + point.x as u32;
+                    self.pixels[index as usize] = color;
+                } else {
+                    self.outside += 1;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    // everything has to stay on these, the sh1107 is drawn at 128x64 like the others
+    const SUPPORTED: &[(u32, u32)] = &[(128, 64), (128, 128), (256, 64)];
+
+    // panels we drive, sideways ones, and a few that nobody makes
+    const SIZES: &[(u32, u32)] = &[
+        (128, 64),
+        (128, 32),
+        (128, 128),
+        (64, 128),
+        (64, 48),
+        (72, 40),
+        (96, 16),
+        (256, 64),
+        (32, 32),
+    ];
+
+    fn scenes<C: Palette>(width: u32, height: u32) -> Vec<Canvas<C>> {
+        let mut random = Random::new(7);
+        let seed = Seed::random(&mut random);
+        let start = Start::new(seed, Some(seed));
+        let mut travel = TravelState::new(&mut random);
+        // get the lane strips moving
+        for _ in 0..3 {
+            travel.tick();
+        }
+        let lock = LockState::new(123, &mut random);
+
+        let mut canvases = Vec::new();
+        let mut draw = |f: &dyn Fn(&mut Canvas<C>)| {
+            let mut canvas = Canvas::new(width, height);
+            f(&mut canvas);
+            canvases.push(canvas);
+        };
+        draw(&|c| start.draw_big_screen(c).unwrap());
+        draw(&|c| start.draw_small_screen(c).unwrap());
+        draw(&|c| travel.draw_big_screen(c).unwrap());
+        draw(&|c| travel.draw_small_screen(c).unwrap());
+        draw(&|c| lock.draw_big_screen(c).unwrap());
+        draw(&|c| lock.draw_small_screen(c).unwrap());
+        // both views on one panel
+        draw(&|c| {
+            let (left, right) = viewport::halves(c.size);
+            travel
+                .draw_big_screen(&mut Viewport::new(c, left, viewport::HALF_SCALE))
+                .unwrap();
+            lock.draw_small_screen(&mut Viewport::new(c, right, viewport::HALF_SCALE))
+                .unwrap();
+        });
+        canvases
+    }
+
+    fn check_sizes<C: Palette>() {
+        for &(width, height) in SIZES {
+            let canvases = scenes::<C>(width, height);
+            for (index, canvas) in canvases.iter().enumerate() {
+                assert!(
+                    canvas.lit() > 0,
+                    "scene {index} is empty at {width}x{height}"
+                );
+            }
+            // shrunk views never spill over into the other half
+            assert_eq!(canvases.last().unwrap().outside, 0, "at {width}x{height}");
+        }
+    }
+
+    #[test]
+    fn scenes_draw_at_any_size() {
+        check_sizes::<BinaryColor>();
+        check_sizes::<Gray4>();
+    }
+
+    #[test]
+    fn scenes_fit_the_panels_we_support() {
+        for &(width, height) in SUPPORTED {
+            for (index, canvas) in scenes::<BinaryColor>(width, height).iter().enumerate() {
+                assert_eq!(canvas.outside, 0, "scene {index} at {width}x{height}");
+            }
+        }
+    }
 }
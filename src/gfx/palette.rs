@@ -0,0 +1,31 @@
+use embedded_graphics::pixelcolor::{BinaryColor, Gray4, GrayColor, PixelColor};
+
+// the colors scenes draw with, so they work on monochrome and grayscale panels alike
+pub trait Palette: PixelColor + From<BinaryColor> {
+    const BACKGROUND: Self;
+    const FOREGROUND: Self;
+    // details that shouldn't draw attention, like the streets on the map
+    const DIMMED: Self;
+    // the shaded side of round things, like the lock pins
+    const SHADE: Self;
+    // fills the steps of diagonal strokes in text, nothing at all without shades
+    const EDGE: Self;
+}
+
+// without shades everything that isn't background is on
+impl Palette for BinaryColor {
+    const BACKGROUND: Self = BinaryColor::Off;
+    const FOREGROUND: Self = BinaryColor::On;
+    const DIMMED: Self = BinaryColor::On;
+    const SHADE: Self = BinaryColor::On;
+    const EDGE: Self = BinaryColor::Off;
+}
+
+// for 16 level panels like the ssd1322
+impl Palette for Gray4 {
+    const BACKGROUND: Self = Gray4::BLACK;
+    const FOREGROUND: Self = Gray4::WHITE;
+    const DIMMED: Self = Gray4::new(6);
+    const SHADE: Self = Gray4::new(10);
+    const EDGE: Self = Gray4::new(5);
+}
@@ -0,0 +1,152 @@
+use embedded_graphics::{
+    image::{ImageDrawableExt, ImageRaw, SubImage},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+
+// multiple sprites of the same size, stacked on top of each other
+pub struct SpriteSheet {
+    raw: ImageRaw<'static, BinaryColor>,
+    size: Size,
+}
+
+impl SpriteSheet {
+    pub const fn new(data: &'static [u8], size: Size) -> Self {
+        Self {
+            raw: ImageRaw::new(data, size.width),
+            size,
+        }
+    }
+
+    pub fn sprite(&self, index: u8) -> SubImage<'_, ImageRaw<'static, BinaryColor>> {
+        let top = index as u32 * self.size.height;
+        self.raw
+            .sub_image(&Rectangle::new(Point::new(0, top as i32), self.size))
+    }
+}
+
+pub enum Playback {
+    Loop,
+    Once,
+}
+
+pub struct Frame<T: 'static> {
+    pub value: T,
+    pub ticks: u8,
+}
+
+pub struct Clip<T: 'static> {
+    pub frames: &'static [Frame<T>],
+    pub playback: Playback,
+}
+
+#[derive(Clone)]
+pub struct Animation<T: 'static> {
+    clip: &'static Clip<T>,
+    frame: usize,
+    remaining: u8,
+}
+
+impl<T: Copy> Animation<T> {
+    pub const fn new(clip: &'static Clip<T>) -> Self {
+        Self {
+            clip,
+            frame: 0,
+            remaining: clip.frames[0].ticks,
+        }
+    }
+
+    // always restarts the clip, even if it's already playing
+    pub const fn play(&mut self, clip: &'static Clip<T>) {
+        *self = Self::new(clip);
+    }
+
+    pub fn tick(&mut self) {
+        self.remaining = self.remaining.saturating_sub(1);
+        if self.remaining > 0 {
+            return;
+        }
+
+        if self.frame + 1 < self.clip.frames.len() {
+            self.frame += 1;
+        } else {
+            match self.clip.playback {
+                Playback::Loop => self.frame = 0,
+                // stay on the last frame
+                Playback::Once => return,
+            }
+        }
+        self.remaining = self.clip.frames[self.frame].ticks;
+    }
+
+    pub const fn is_finished(&self) -> bool {
+        matches!(self.clip.playback, Playback::Once)
+            && self.frame + 1 >= self.clip.frames.len()
+            && self.remaining == 0
+    }
+
+    pub const fn current(&self) -> T {
+        self.clip.frames[self.frame].value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::mock_display::MockDisplay;
+
+    static CLIP: Clip<u8> = Clip {
+        frames: &[Frame { value: 1, ticks: 2 }, Frame { value: 2, ticks: 1 }],
+        playback: Playback::Loop,
+    };
+    static ONCE: Clip<u8> = Clip {
+        frames: &[Frame { value: 1, ticks: 1 }, Frame { value: 2, ticks: 2 }],
+        playback: Playback::Once,
+    };
+
+    fn values(animation: &mut Animation<u8>, ticks: usize) -> Vec<u8> {
+        (0..ticks)
+            .map(|_| {
+                let value = animation.current();
+                animation.tick();
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loops_wrap_around() {
+        let mut animation = Animation::new(&CLIP);
+        assert_eq!(values(&mut animation, 7), [1, 1, 2, 1, 1, 2, 1]);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn once_stays_on_the_last_frame() {
+        let mut animation = Animation::new(&ONCE);
+        assert_eq!(values(&mut animation, 3), [1, 2, 2]);
+        assert!(animation.is_finished());
+        assert_eq!(values(&mut animation, 3), [2, 2, 2]);
+
+        // playing it again starts over
+        animation.play(&ONCE);
+        assert!(!animation.is_finished());
+        assert_eq!(animation.current(), 1);
+    }
+
+    #[test]
+    fn sprites_come_from_the_stacked_frames() {
+        // two 8x2 frames, the first one lit
+        static DATA: [u8; 4] = [0xff, 0xff, 0, 0];
+        let sheet = SpriteSheet::new(&DATA, Size::new(8, 2));
+        for (index, color) in [(0, BinaryColor::On), (1, BinaryColor::Off)] {
+            let mut display = MockDisplay::new();
+            sheet.sprite(index).draw(&mut display).unwrap();
+            let area = Rectangle::new(Point::zero(), Size::new(8, 2));
+            assert!(area
+                .points()
+                .all(|point| display.get_pixel(point) == Some(color)));
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use crate::game::Screen;
-use crate::gfx;
+use crate::gfx::sprite::{Animation, Clip, Frame, Playback};
+use crate::gfx::{self, Palette, TextStyle};
 use crate::i10n;
-use core::fmt::Debug;
+use crate::seed::{self, Seed};
+use core::str;
 use embedded_graphics::{
     draw_target::DrawTarget,
-    mono_font::{ascii, MonoTextStyle, MonoTextStyleBuilder},
-    pixelcolor::BinaryColor,
+    mono_font::{ascii, MonoFont},
     prelude::*,
     text::{Baseline, Text},
 };
@@ -15,71 +16,191 @@ const COOLDOWN: u8 = 2;
 const ALIVENESS_MODULO: u8 = 4;
 const ALIVENESS_SLOWDOWN: u8 = 3;
 
-pub const BIG_TEXT: MonoTextStyle<BinaryColor> = MonoTextStyleBuilder::new()
-    .font(&ascii::FONT_8X13)
-    .text_color(BinaryColor::On)
-    .build();
+const TITLE_Y_OFFSET: i32 = 15;
+// the small print sits at the bottom
+const DEDICATION_BOTTOM_OFFSET: i32 = 21;
+const YEAR_BOTTOM_OFFSET: i32 = 14;
+// the codes go between the title and the small print
+const CODE_Y_OFFSET: i32 = 30;
+const LAST_CODE_Y_OFFSET: i32 = 37;
+// let the title hop every now and then
+static TITLE_HOP: Clip<i32> = Clip {
+    frames: &[
+        Frame {
+            value: 0,
+            ticks: 30,
+        },
+        Frame {
+            value: -1,
+            ticks: 2,
+        },
+        Frame {
+            value: -2,
+            ticks: 2,
+        },
+        Frame {
+            value: -1,
+            ticks: 2,
+        },
+    ],
+    playback: Playback::Loop,
+};
+
+pub const BIG_FONT: &MonoFont = &ascii::FONT_8X13;
+
+pub const fn big_text<C: Palette>() -> TextStyle<C> {
+    TextStyle::new(BIG_FONT)
+}
 
+#[derive(Clone)]
 pub struct Start {
     cooldown: u8,
     aliveness: u8,
+    title: Animation<i32>,
+    // the code of the next run
+    seed: Seed,
+    // the code of the run that just ended
+    last: Option<Seed>,
+    // the letter being entered, if any
+    cursor: Option<usize>,
     pub transition: Option<Screen>,
+    // asks for the newest replay
+    pub replay: bool,
 }
 
 impl Start {
-    pub const fn new() -> Self {
+    pub const fn new(seed: Seed, last: Option<Seed>) -> Self {
         Self {
             cooldown: COOLDOWN,
             aliveness: 0,
+            title: Animation::new(&TITLE_HOP),
+            seed,
+            last,
+            cursor: None,
             transition: None,
+            replay: false,
         }
     }
 
+    pub const fn seed(&self) -> Seed {
+        self.seed
+    }
+
     pub fn tick(&mut self) {
         self.cooldown = self.cooldown.saturating_sub(1);
         self.aliveness = (self.aliveness + 1) % (ALIVENESS_MODULO * ALIVENESS_SLOWDOWN);
+        self.title.tick();
     }
 
     pub fn button_action(&mut self) {
-        if self.cooldown == 0 {
-            self.transition = Some(Screen::Travel);
+        if self.cooldown != 0 {
+            return;
+        }
+
+        // confirm the letter being entered, or start with the code as it is
+        match self.cursor {
+            Some(index) => self.cursor = (index + 1 < seed::CODE_LENGTH).then_some(index + 1),
+            None => self.transition = Some(Screen::Travel),
+        }
+    }
+
+    pub fn button_up(&mut self) {
+        self.change_letter(1);
+    }
+
+    pub fn button_down(&mut self) {
+        if self.cursor.is_some() {
+            self.change_letter(seed::DIGITS - 1);
+        } else if self.cooldown == 0 {
+            self.replay = true;
+        }
+    }
+
+    fn change_letter(&mut self, step: u8) {
+        if self.cooldown != 0 {
+            return;
         }
+
+        // the first press starts entering a code from its first letter
+        let index = *self.cursor.get_or_insert(0);
+        let digit = (self.seed.digit(index) + step) % seed::DIGITS;
+        self.seed = self.seed.with_digit(index, digit);
     }
 
-    pub fn draw_big_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub fn draw_big_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let (width, height) = (gfx::width(display), gfx::height(display));
         for (text, style, y) in [
-            ("Taco Burglar", BIG_TEXT, 15),
-            (".: Happy Birthday Ria :.", gfx::TEXT_STYLE, 43),
-            ("2025", gfx::TEXT_STYLE, 50),
+            (
+                "Taco Burglar",
+                big_text(),
+                TITLE_Y_OFFSET + self.title.current(),
+            ),
+            (
+                ".: Happy Birthday Ria :.",
+                gfx::text_style(),
+                height - DEDICATION_BOTTOM_OFFSET,
+            ),
+            ("2025", gfx::text_style(), height - YEAR_BOTTOM_OFFSET),
         ] {
             Text::with_baseline(
                 text,
-                Point::new(
-                    gfx::text_align_center(text, gfx::DISPLAY_WIDTH, style.font),
-                    y,
-                ),
+                Point::new(gfx::text_align_center(text, width, style.font), y),
                 style,
                 Baseline::Top,
             )
-            .draw(display)
-            .unwrap();
+            .draw(display)?;
+        }
+
+        // blink the letter being entered
+        let mut code = self.seed.code();
+        if let Some(index) = self.cursor {
+            if (self.aliveness / ALIVENESS_SLOWDOWN) % 2 == 1 {
+                code[index] = b'_';
+            }
+        }
+        draw_code(display, i10n::CODE, &code, CODE_Y_OFFSET)?;
+        if let Some(last) = self.last {
+            draw_code(display, i10n::LAST_CODE, &last.code(), LAST_CODE_Y_OFFSET)?;
         }
+
+        Ok(())
     }
 
-    pub fn draw_small_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub fn draw_small_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
         for (num, text) in i10n::INSTRUCTIONS.iter().enumerate() {
             let text = text[(self.aliveness / ALIVENESS_SLOWDOWN) as usize % text.len()];
             let num = num as i32;
-            let y = num * (gfx::TEXT_STYLE.font.character_size.height + 1) as i32;
-            Text::with_baseline(text, Point::new(0, y), gfx::TEXT_STYLE, Baseline::Top)
-                .draw(display)
-                .unwrap();
+            let y = num * (gfx::TEXT_FONT.character_size.height + 1) as i32;
+            Text::with_baseline(text, Point::new(0, y), gfx::text_style(), Baseline::Top)
+                .draw(display)?;
         }
+
+        Ok(())
     }
 }
+
+// a label and a code next to each other, centered
+fn draw_code<C: Palette, D: DrawTarget<Color = C>>(
+    display: &mut D,
+    label: &str,
+    code: &[u8; seed::CODE_LENGTH],
+    y: i32,
+) -> Result<(), D::Error> {
+    let code = str::from_utf8(code).unwrap_or("");
+    let left = gfx::centered(
+        gfx::width(display),
+        ((label.len() + 1 + code.len()) * gfx::CHAR_WIDTH) as u32,
+    );
+    Text::with_baseline(label, Point::new(left, y), gfx::text_style(), Baseline::Top)
+        .draw(display)?;
+    let left = left + ((label.len() + 1) * gfx::CHAR_WIDTH) as i32;
+    Text::with_baseline(code, Point::new(left, y), gfx::text_style(), Baseline::Top)
+        .draw(display)?;
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use crate::gfx::Palette;
+use core::marker::PhantomData;
+use embedded_graphics::{
+    mono_font::{MonoFont, MonoTextStyle, MonoTextStyleBuilder},
+    prelude::*,
+    primitives::Rectangle,
+    text::{
+        renderer::{TextMetrics, TextRenderer},
+        Baseline,
+    },
+};
+
+// the fonts are at most this tall, anything past the mask is drawn without soft edges
+const MAX_HEIGHT: usize = 16;
+
+// monospaced text that gets soft edges on grayscale panels, plain on monochrome ones
+#[derive(Clone, Copy)]
+pub struct TextStyle<C> {
+    pub font: &'static MonoFont<'static>,
+    color: PhantomData<C>,
+}
+
+impl<C: Palette> TextStyle<C> {
+    pub const fn new(font: &'static MonoFont<'static>) -> Self {
+        Self {
+            font,
+            color: PhantomData,
+        }
+    }
+
+    const fn mono(&self) -> MonoTextStyle<'static, C> {
+        MonoTextStyleBuilder::new()
+            .font(self.font)
+            .text_color(C::FOREGROUND)
+            .build()
+    }
+}
+
+impl<C: Palette> TextRenderer for TextStyle<C> {
+    type Color = C;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let mono = self.mono();
+        if C::EDGE == C::BACKGROUND {
+            return mono.draw_string(text, position, baseline, target);
+        }
+
+        // the edges depend on the neighbouring pixels, so collect the glyphs first
+        let area = mono.measure_string(text, position, baseline).bounding_box;
+        let mut mask = Mask {
+            target,
+            bits: Bits {
+                area,
+                rows: [0; MAX_HEIGHT],
+            },
+        };
+        let next = mono.draw_string(text, position, baseline, &mut mask)?;
+        mask.finish()?;
+        Ok(next)
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.mono()
+            .draw_whitespace(width, position, baseline, target)
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        self.mono().measure_string(text, position, baseline)
+    }
+
+    fn line_height(&self) -> u32 {
+        self.mono().line_height()
+    }
+}
+
+// the lit pixels of a line of text, one bit each
+struct Bits {
+    area: Rectangle,
+    rows: [u128; MAX_HEIGHT],
+}
+
+impl Bits {
+    fn spot(&self, point: Point) -> Option<(usize, u32)> {
+        let offset = point - self.area.top_left;
+        let (x, y) = (
+            usize::try_from(offset.x).ok()?,
+            usize::try_from(offset.y).ok()?,
+        );
+        (x < u128::BITS as usize && y < MAX_HEIGHT).then_some((y, x as u32))
+    }
+
+    fn is_lit(&self, point: Point) -> bool {
+        self.spot(point)
+            .is_some_and(|(row, bit)| self.rows[row] & (1 << bit) != 0)
+    }
+
+    // a gap in a diagonal line, between two lit pixels that only touch at the corners
+    fn is_edge(&self, point: Point) -> bool {
+        let lit = |x, y| self.is_lit(point + Point::new(x, y));
+        !lit(0, 0)
+            && [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+                .into_iter()
+                .any(|(x, y)| lit(x, 0) && lit(0, y) && !lit(x, y))
+    }
+}
+
+// collects the glyphs before anything reaches the target
+struct Mask<'a, D> {
+    target: &'a mut D,
+    bits: Bits,
+}
+
+impl<D: DrawTarget> Mask<'_, D> {
+    fn finish(self) -> Result<(), D::Error>
+    where
+        D::Color: Palette,
+    {
+        let Mask { target, bits } = self;
+        let pixels = bits.area.points().filter_map(|point| {
+            if bits.is_lit(point) {
+                Some(Pixel(point, D::Color::FOREGROUND))
+            } else if bits.spot(point).is_some() && bits.is_edge(point) {
+                Some(Pixel(point, D::Color::EDGE))
+            } else {
+                None
+            }
+        });
+        target.draw_iter(pixels)
+    }
+}
+
+impl<D: DrawTarget> Dimensions for Mask<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Mask<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        // whatever doesn't fit goes straight through
+        let bits = &mut self.bits;
+        self.target
+            .draw_iter(pixels.into_iter().filter(|Pixel(point, _)| {
+                let Some((row, bit)) = bits.spot(*point) else {
+                    return true;
+                };
+                bits.rows[row] |= 1 << bit;
+                false
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::start::BIG_FONT;
+    use crate::gfx::tests::Canvas;
+    use crate::gfx::TEXT_FONT;
+    use embedded_graphics::pixelcolor::{BinaryColor, Gray4};
+    use embedded_graphics::text::Text;
+
+    const SAMPLES: &[&str] = &["Taco Burglar", "0123456789 tacos", "xyz/VWAK"];
+
+    fn draw<C: Palette, S: TextRenderer<Color = C>>(style: S, text: &str) -> Canvas<C> {
+        let mut canvas = Canvas::new(128, 20);
+        Text::with_baseline(text, Point::new(1, 1), style, Baseline::Top)
+            .draw(&mut canvas)
+            .unwrap();
+        canvas
+    }
+
+    fn plain<C: Palette>(font: &'static MonoFont<'static>) -> MonoTextStyle<'static, C> {
+        MonoTextStyleBuilder::new()
+            .font(font)
+            .text_color(C::FOREGROUND)
+            .build()
+    }
+
+    #[test]
+    fn monochrome_text_stays_the_same() {
+        for font in [TEXT_FONT, BIG_FONT] {
+            for text in SAMPLES {
+                let smooth = draw(TextStyle::<BinaryColor>::new(font), text);
+                assert_eq!(smooth.pixels, draw(plain(font), text).pixels);
+            }
+        }
+    }
+
+    #[test]
+    fn grayscale_text_gets_soft_edges() {
+        for font in [TEXT_FONT, BIG_FONT] {
+            for text in SAMPLES {
+                let smooth = draw(TextStyle::<Gray4>::new(font), text);
+                let plain = draw(plain(font), text);
+                let mut edges = 0;
+                for (smooth, plain) in smooth.pixels.iter().zip(&plain.pixels) {
+                    if *smooth == Gray4::EDGE {
+                        // only ever next to the strokes, never on top of them
+                        assert_eq!(*plain, Gray4::BACKGROUND);
+                        edges += 1;
+                    } else {
+                        assert_eq!(smooth, plain);
+                    }
+                }
+                assert!(edges > 0, "{text}");
+            }
+        }
+    }
+}
@@ -1,11 +1,11 @@
-use crate::game::Screen;
-use crate::gfx;
+use crate::game::{Event, Events, Screen};
+use crate::gfx::sprite::{Animation, Clip, Frame, Playback, SpriteSheet};
+use crate::gfx::{self, Palette};
+use core::cell::Cell;
 use core::cmp;
-use core::fmt::Debug;
 use embedded_graphics::{
     draw_target::DrawTarget,
-    image::{Image, ImageRaw},
-    pixelcolor::BinaryColor,
+    image::Image,
     prelude::*,
     primitives::Rectangle,
     text::{Baseline, Text},
@@ -13,14 +13,53 @@ use embedded_graphics::{
 use rand::Rng;
 use rand_core::RngCore;
 
-const BIKE: ImageRaw<BinaryColor> = ImageRaw::new(include_bytes!("../../video/bike.raw"), 24);
-
-// small screen consts
-const MAP_POINT: Point = Point::new(
-    gfx::centered(gfx::DISPLAY_WIDTH, CELL_SIZE * MAP_X as u32),
-    gfx::centered(gfx::DISPLAY_HEIGHT, CELL_SIZE * MAP_Y as u32),
+const BIKE: SpriteSheet = SpriteSheet::new(
+    include_bytes!("../../video/bike-sheet.raw"),
+    Size::new(24, 14),
 );
+const BIKE_STILL: u8 = 0;
+const BIKE_SPOKES_A: u8 = 1;
+const BIKE_SPOKES_B: u8 = 2;
+const BIKE_LEAN_FORWARD: u8 = 3;
+const BIKE_LEAN_BACK: u8 = 4;
+
+static BIKE_PEDAL: Clip<u8> = Clip {
+    frames: &[
+        Frame {
+            value: BIKE_SPOKES_A,
+            ticks: 2,
+        },
+        Frame {
+            value: BIKE_STILL,
+            ticks: 1,
+        },
+        Frame {
+            value: BIKE_SPOKES_B,
+            ticks: 2,
+        },
+        Frame {
+            value: BIKE_STILL,
+            ticks: 1,
+        },
+    ],
+    playback: Playback::Loop,
+};
+static BIKE_LEAN_UP: Clip<u8> = Clip {
+    frames: &[Frame {
+        value: BIKE_LEAN_FORWARD,
+        ticks: 3,
+    }],
+    playback: Playback::Once,
+};
+static BIKE_LEAN_DOWN: Clip<u8> = Clip {
+    frames: &[Frame {
+        value: BIKE_LEAN_BACK,
+        ticks: 3,
+    }],
+    playback: Playback::Once,
+};
 
+// small screen consts
 const TICKS_PER_TRAVEL_SQUARE: u8 = 7;
 
 const CELL_SIZE: u32 = 5;
@@ -91,6 +130,53 @@ const MAP: Map = Map([
     [O, X, X, X, X, X, O, O, O, X, X, X, X, X, X],
 ]);
 
+// the streets never change, so they are baked into a 1-bit image at compile time
+const MAP_WIDTH: usize = MAP_X * CELL_SIZE as usize;
+const MAP_HEIGHT: usize = MAP_Y * CELL_SIZE as usize;
+const MAP_STRIDE: usize = MAP_WIDTH.div_ceil(8);
+static MAP_IMAGE: [u8; MAP_STRIDE * MAP_HEIGHT] = bake_map();
+
+const fn set_street(image: &mut [u8; MAP_STRIDE * MAP_HEIGHT], x: usize, y: usize) {
+    image[y * MAP_STRIDE + x / 8] |= 0x80 >> (x % 8);
+}
+
+const fn bake_map() -> [u8; MAP_STRIDE * MAP_HEIGHT] {
+    let mut image = [0; MAP_STRIDE * MAP_HEIGHT];
+    let cells = &MAP.0;
+    let sub = SUB_CELL_SIZE as usize;
+    let mut y = 0;
+    while y < MAP_Y {
+        let mut x = 0;
+        while x < MAP_X {
+            if cells[y][x] {
+                let (left, top) = (x * CELL_SIZE as usize, y * CELL_SIZE as usize);
+                // center
+                set_street(&mut image, left + sub, top + sub);
+                // lines towards the neighbours
+                let mut i = 0;
+                while i < sub {
+                    if y > 0 && cells[y - 1][x] {
+                        set_street(&mut image, left + sub, top + i);
+                    }
+                    if y + 1 < MAP_Y && cells[y + 1][x] {
+                        set_street(&mut image, left + sub, top + sub + 1 + i);
+                    }
+                    if x > 0 && cells[y][x - 1] {
+                        set_street(&mut image, left + i, top + sub);
+                    }
+                    if x + 1 < MAP_X && cells[y][x + 1] {
+                        set_street(&mut image, left + sub + 1 + i, top + sub);
+                    }
+                    i += 1;
+                }
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+    image
+}
+
 // big screen consts
 pub const NUM_LANES: u8 = 3;
 const MIDDLE_STRIP_LENGTH: u8 = 5;
@@ -99,13 +185,14 @@ const MIDDLE_STRIP_STEP_SIZE: u8 = 3;
 const LANE_HEIGHT: u32 = 18;
 
 const BIKE_Y_OFFSET: u32 = 3;
-const FIRST_LANE_TOP_OFFSET: i32 =
-    gfx::DISPLAY_HEIGHT - (LANE_HEIGHT as i32 + 1) * NUM_LANES as i32;
-const SECOND_LANE_TOP_OFFSET: i32 = FIRST_LANE_TOP_OFFSET + (LANE_HEIGHT as i32 + 1);
-const THIRD_LANE_TOP_OFFSET: i32 = SECOND_LANE_TOP_OFFSET + (LANE_HEIGHT as i32 + 1);
 const BIKE_LEFT_OFFSET: i32 = 13;
 
-#[derive(Clone, Copy)]
+// the lanes are stacked on the bottom of the screen
+const fn lane_top_offset(height: i32, lane: u8) -> i32 {
+    height - (LANE_HEIGHT as i32 + 1) * (NUM_LANES - lane) as i32
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Direction {
     North,
     East,
@@ -142,9 +229,69 @@ impl Direction {
     }
 }
 
-enum LineOrientation {
-    Horizontal,
-    Vertical,
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+// every place and heading the bike can be in
+const NUM_STATES: usize = MAP_X * MAP_Y * DIRECTIONS.len();
+
+const fn state_index((x, y): (usize, usize), direction: Direction) -> usize {
+    (y * MAP_X + x) * DIRECTIONS.len() + direction as usize
+}
+
+const fn state_from_index(index: usize) -> ((usize, usize), Direction) {
+    let square = index / DIRECTIONS.len();
+    (
+        (square % MAP_X, square / MAP_X),
+        DIRECTIONS[index % DIRECTIONS.len()],
+    )
+}
+
+// try to turn in the direction of the lane, if possible, then drive on
+fn steer(player: (usize, usize), direction: Direction, lane: u8) -> ((usize, usize), Direction) {
+    let new_direction = match lane {
+        0 => direction.turn_counter_clockwise(),
+        2 => direction.turn_clockwise(),
+        _ => direction,
+    };
+    let (x, y) = player;
+    if MAP.direction(x, y, new_direction).is_some() {
+        drive(player, new_direction)
+    } else {
+        drive(player, direction)
+    }
+}
+
+fn drive(player: (usize, usize), mut direction: Direction) -> ((usize, usize), Direction) {
+    let (x, y) = player;
+    loop {
+        // check if we can drive that way
+        if let Some(pos) = MAP.direction(x, y, direction) {
+            return (pos, direction);
+        }
+
+        // the loop didn't return, check if we can do a clockwise turn
+        let new_direction = direction.turn_clockwise();
+        if MAP.direction(x, y, new_direction).is_some() {
+            return (player, new_direction);
+        }
+
+        // else, check if we can do a counter clockwise turn
+        let new_direction = direction.turn_counter_clockwise();
+        if MAP.direction(x, y, new_direction).is_some() {
+            return (player, new_direction);
+        }
+
+        // else, always do two clockwise to turn around
+        direction = direction.turn_clockwise().turn_clockwise();
+    }
+}
+
+fn is_road((x, y): (usize, usize)) -> bool {
+    MAP.get(x, y)
 }
 
 fn random_valid_position<R: RngCore>(mut random: R) -> (usize, usize) {
@@ -152,12 +299,13 @@ fn random_valid_position<R: RngCore>(mut random: R) -> (usize, usize) {
         let num = random.gen_range(0..MAP_X * MAP_Y);
         let y = num / MAP_X;
         let x = num - (y * MAP_X);
-        if MAP.get(x, y) {
+        if is_road((x, y)) {
             return (x, y);
         }
     }
 }
 
+#[derive(Clone)]
 pub struct TravelState {
     pub score: u32,
     goal: (usize, usize),
@@ -166,6 +314,10 @@ pub struct TravelState {
     next_square: u8,
     active_lane: u8,
     middle_strip: u8,
+    bike: Animation<u8>,
+    // the search is too big to run every tick, it only changes with the square or the goal
+    towards_goal: Cell<Option<u8>>,
+    pub events: Events,
     pub transition: Option<Screen>,
 }
 
@@ -179,6 +331,9 @@ impl TravelState {
             next_square: TICKS_PER_TRAVEL_SQUARE,
             active_lane: 1,
             middle_strip: 0,
+            bike: Animation::new(&BIKE_PEDAL),
+            towards_goal: Cell::new(None),
+            events: Events::default(),
             transition: None,
         };
         state.set_random_player(&mut random);
@@ -188,6 +343,7 @@ impl TravelState {
 
     pub fn set_random_player<R: RngCore>(&mut self, random: R) {
         self.player = random_valid_position(random);
+        self.towards_goal.set(None);
     }
 
     pub fn set_random_goal<R: RngCore>(&mut self, mut random: R) {
@@ -198,49 +354,69 @@ impl TravelState {
                 break;
             }
         }
+        self.towards_goal.set(None);
     }
 
-    // try to turn in the selected direction, if possible
-    fn try_turn(&mut self, new_direction: Direction) {
-        let (x, y) = self.player;
-        if MAP.direction(x, y, new_direction).is_some() {
-            self.direction = new_direction;
-        }
+    pub const fn active_lane(&self) -> u8 {
+        self.active_lane
     }
 
-    fn drive(&mut self) {
-        loop {
-            let (x, y) = self.player;
-            // check if we can drive that way
-            if let Some(pos) = MAP.direction(x, y, self.direction) {
-                self.player = pos;
-                break;
-            }
+    // the lane to be in at the next square, for the shortest way to the goal
+    pub fn lane_towards_goal(&self) -> u8 {
+        if let Some(lane) = self.towards_goal.get() {
+            return lane;
+        }
+        let lane = self.search_towards_goal();
+        self.towards_goal.set(Some(lane));
+        lane
+    }
 
-            // the loop didn't break, check if we can do a clockwise turn
-            let new_direction = self.direction.turn_clockwise();
-            if MAP.direction(x, y, new_direction).is_some() {
-                self.direction = new_direction;
-                break;
-            }
+    fn search_towards_goal(&self) -> u8 {
+        let mut seen = [false; NUM_STATES];
+        let mut queue = [0u16; NUM_STATES];
+        // the lane taken at the first square, for every state we've seen
+        let mut first = [0u8; NUM_STATES];
+        let (mut head, mut tail) = (0, 0);
 
-            // else, check if we can do a counter clockwise turn
-            let new_direction = self.direction.turn_counter_clockwise();
-            if MAP.direction(x, y, new_direction).is_some() {
-                self.direction = new_direction;
-                break;
+        // every square can be taken in three ways, search them breadth first
+        let (mut player, mut direction, mut lane) = (self.player, self.direction, None);
+        loop {
+            for next_lane in 0..NUM_LANES {
+                let (next_player, next_direction) = steer(player, direction, next_lane);
+                let first_lane = lane.unwrap_or(next_lane);
+                if next_player == self.goal {
+                    return first_lane;
+                }
+                let state = state_index(next_player, next_direction);
+                if !seen[state] {
+                    seen[state] = true;
+                    first[state] = first_lane;
+                    queue[tail] = state as u16;
+                    tail += 1;
+                }
             }
 
-            // else, always do two clockwise to turn around
-            self.direction = self.direction.turn_clockwise().turn_clockwise();
+            // the goal is always on the road, we don't run out of places to go
+            let Some(&state) = queue[..tail].get(head) else {
+                return 1;
+            };
+            head += 1;
+            (player, direction) = state_from_index(state as usize);
+            lane = Some(first[state as usize]);
         }
     }
 
     pub fn button_up(&mut self) {
+        if self.active_lane > 0 {
+            self.bike.play(&BIKE_LEAN_UP);
+        }
         self.active_lane = self.active_lane.saturating_sub(1);
     }
 
     pub fn button_down(&mut self) {
+        if self.active_lane < NUM_LANES - 1 {
+            self.bike.play(&BIKE_LEAN_DOWN);
+        }
         self.active_lane = cmp::min(self.active_lane + 1, NUM_LANES - 1);
     }
 
@@ -249,53 +425,61 @@ impl TravelState {
         self.middle_strip += MIDDLE_STRIP_STEP_SIZE;
         self.middle_strip %= MIDDLE_STRIP_LENGTH + MIDDLE_STRIP_GAP;
 
+        self.bike.tick();
+        if self.bike.is_finished() {
+            self.bike.play(&BIKE_PEDAL);
+        }
+
         // check if next square is reached
         self.next_square = self.next_square.saturating_sub(1);
         if self.next_square == 0 {
             // reset counter
             self.next_square = TICKS_PER_TRAVEL_SQUARE;
+            let old_direction = self.direction;
 
-            // do turn
-            self.try_turn(match self.active_lane {
-                0 => self.direction.turn_counter_clockwise(),
-                2 => self.direction.turn_clockwise(),
-                _ => self.direction,
-            });
-
-            // drive in current direction
-            self.drive();
+            // do turn and drive in the new direction
+            (self.player, self.direction) = steer(self.player, self.direction, self.active_lane);
+            self.towards_goal.set(None);
 
             if self.player == self.goal {
                 // we want to switch to lock mini game
                 self.transition = Some(Screen::Lock);
+                self.events.push(Event::GoalReached);
+            } else if self.direction != old_direction {
+                self.events.push(Event::Turn);
             }
         }
     }
 
     // render code
 
-    pub fn draw_lane<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D, y: i32, full: bool)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub fn draw_lane<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+        y: i32,
+        full: bool,
+    ) -> Result<(), D::Error> {
         let lane_point = Point::new(0, y);
+        let width = gfx::width(display);
 
         if full {
-            Rectangle::new(lane_point, Size::new(gfx::DISPLAY_WIDTH as u32, 1))
-                .into_styled(gfx::WHITE)
-                .draw(display)
-                .unwrap();
+            Rectangle::new(lane_point, Size::new(width as u32, 1))
+                .into_styled(gfx::white())
+                .draw(display)?;
         } else {
             let mut x = -(self.middle_strip as i32);
-            while x < gfx::DISPLAY_WIDTH {
-                // render current strip
+            while x < width {
+                // render current strip, cut off at the edges
+                let (left, right) = (
+                    cmp::max(x, 0),
+                    cmp::min(x + MIDDLE_STRIP_LENGTH as i32, width),
+                );
                 Rectangle::new(
-                    lane_point + Point::new(x, 0),
-                    Size::new(MIDDLE_STRIP_LENGTH as u32, 1),
+                    lane_point + Point::new(left, 0),
+                    Size::new(cmp::max(right - left, 0) as u32, 1),
                 )
-                .into_styled(gfx::WHITE)
-                .draw(display)
-                .unwrap();
+                .into_styled(gfx::white())
+                .draw(display)?;
 
                 // add rendered strip
                 x += MIDDLE_STRIP_LENGTH as i32;
@@ -303,134 +487,202 @@ impl TravelState {
                 x += MIDDLE_STRIP_GAP as i32;
             }
         }
+
+        Ok(())
     }
 
-    pub fn draw_big_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    pub fn draw_big_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let height = gfx::height(display);
+
         // render lanes
-        self.draw_lane(display, FIRST_LANE_TOP_OFFSET, true);
-        self.draw_lane(display, SECOND_LANE_TOP_OFFSET, false);
-        self.draw_lane(display, THIRD_LANE_TOP_OFFSET, false);
+        for lane in 0..NUM_LANES {
+            self.draw_lane(display, lane_top_offset(height, lane), lane == 0)?;
+        }
 
         // render bike
         let bike_point = Point::new(
             BIKE_LEFT_OFFSET,
-            match self.active_lane {
-                0 => FIRST_LANE_TOP_OFFSET,
-                1 => SECOND_LANE_TOP_OFFSET,
-                _ => THIRD_LANE_TOP_OFFSET,
-            } + BIKE_Y_OFFSET as i32,
+            lane_top_offset(height, self.active_lane) + BIKE_Y_OFFSET as i32,
         );
-        Image::new(&BIKE, bike_point).draw(display).unwrap();
+        // the sprites are monochrome
+        Image::new(&BIKE.sprite(self.bike.current()), bike_point)
+            .draw(&mut display.color_converted())?;
 
         // render score
-        gfx::render_tacos(display, self.score);
+        gfx::render_tacos(display, self.score)?;
 
         // render direction
         Text::with_baseline(
             self.direction.as_str(),
             Point::new(0, 0),
-            gfx::TEXT_STYLE,
+            gfx::text_style(),
             Baseline::Top,
         )
-        .draw(display)
-        .unwrap();
+        .draw(display)?;
+
+        Ok(())
     }
 
-    #[inline]
-    fn draw_cell_line<D: DrawTarget<Color = BinaryColor>>(
+    pub fn draw_small_screen<C: Palette, D: DrawTarget<Color = C>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let map_point = Point::new(
+            gfx::centered(gfx::width(display), MAP_WIDTH as u32),
+            gfx::centered(gfx::height(display), MAP_HEIGHT as u32),
+        );
+
+        // render streets, only the lit pixels so we don't paint over anything
+        let streets = MAP_IMAGE
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte != 0)
+            .flat_map(|(i, &byte)| {
+                let (x, y) = ((i % MAP_STRIDE * 8) as i32, (i / MAP_STRIDE) as i32);
+                (0..8)
+                    .filter(move |bit| byte & (0x80 >> bit) != 0)
+                    .map(move |bit| Pixel(map_point + Point::new(x + bit, y), C::DIMMED))
+            });
+        display.draw_iter(streets)?;
+
+        for (x, y) in [self.player, self.goal] {
+            let cell_point = map_point
+                + Point::new((x as u32 * CELL_SIZE) as i32, (y as u32 * CELL_SIZE) as i32);
+            // hide the streets of this cell
+            Rectangle::new(cell_point, Size::new(CELL_SIZE, CELL_SIZE))
+                .into_styled(gfx::black())
+                .draw(display)?;
+            // they both share this white rectangle
+            Rectangle::new(cell_point + Point::new(1, 1), Size::new(3, 3))
+                .into_styled(gfx::white())
+                .draw(display)?;
+            // goal has a black dot in the middle
+            if (x, y) == self.goal {
+                Rectangle::new(
+                    cell_point + Point::new(SUB_CELL_SIZE as i32, SUB_CELL_SIZE as i32),
+                    Size::new(1, 1),
+                )
+                .into_styled(gfx::black())
+                .draw(display)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::gfx::tests::Canvas;
+    use crate::seed::Random;
+    use core::convert::Infallible;
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use embedded_graphics::primitives::PrimitiveStyle;
+    use std::time::Instant;
+
+    enum LineOrientation {
+        Horizontal,
+        Vertical,
+    }
+
+    fn draw_cell_line<C: Palette, D: DrawTarget<Color = C>>(
         display: &mut D,
         cell_point: Point,
         pos_x: i32,
         pos_y: i32,
         orientation: LineOrientation,
-    ) where
-        <D as DrawTarget>::Error: Debug,
-    {
+    ) -> Result<(), D::Error> {
         let size = match orientation {
             LineOrientation::Horizontal => Size::new(SUB_CELL_SIZE, 1),
             LineOrientation::Vertical => Size::new(1, SUB_CELL_SIZE),
         };
 
         Rectangle::new(cell_point + Point::new(pos_x, pos_y), size)
-            .into_styled(gfx::WHITE)
-            .draw(display)
-            .unwrap();
+            .into_styled(PrimitiveStyle::with_fill(C::DIMMED))
+            .draw(display)?;
+
+        Ok(())
     }
 
-    pub fn draw_small_screen<D: DrawTarget<Color = BinaryColor>>(&self, display: &mut D)
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+    // how the map was drawn before it got baked, a few rectangles per cell
+    fn draw_map_by_cells<C: Palette, D: DrawTarget<Color = C>>(
+        state: &TravelState,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let map_point = Point::new(
+            gfx::centered(gfx::width(display), CELL_SIZE * MAP_X as u32),
+            gfx::centered(gfx::height(display), CELL_SIZE * MAP_Y as u32),
+        );
+
         for (y, row) in MAP.0.iter().enumerate() {
             for (x, cell) in row.iter().enumerate() {
                 if !*cell {
                     continue;
                 };
 
-                let cell_point = MAP_POINT
+                let cell_point = map_point
                     + Point::new((x as u32 * CELL_SIZE) as i32, (y as u32 * CELL_SIZE) as i32);
 
-                if (x, y) == self.goal || (x, y) == self.player {
+                if (x, y) == state.goal || (x, y) == state.player {
                     // they both share this white rectangle
                     Rectangle::new(cell_point + Point::new(1, 1), Size::new(3, 3))
-                        .into_styled(gfx::WHITE)
-                        .draw(display)
-                        .unwrap();
+                        .into_styled(gfx::white())
+                        .draw(display)?;
                     // goal has a black dot in the middle
-                    if (x, y) == self.goal {
+                    if (x, y) == state.goal {
                         Rectangle::new(
                             cell_point + Point::new(SUB_CELL_SIZE as i32, SUB_CELL_SIZE as i32),
                             Size::new(1, 1),
                         )
-                        .into_styled(gfx::BLACK)
-                        .draw(display)
-                        .unwrap();
+                        .into_styled(gfx::black())
+                        .draw(display)?;
                     }
                     continue;
                 }
 
                 // render lines
                 if MAP.above(x, y).is_some() {
-                    Self::draw_cell_line(
+                    draw_cell_line(
                         display,
                         cell_point,
                         SUB_CELL_SIZE as i32,
                         0,
                         LineOrientation::Vertical,
-                    );
+                    )?;
                 }
 
                 if MAP.below(x, y).is_some() {
-                    Self::draw_cell_line(
+                    draw_cell_line(
                         display,
                         cell_point,
                         SUB_CELL_SIZE as i32,
                         SUB_CELL_SIZE as i32 + 1,
                         LineOrientation::Vertical,
-                    );
+                    )?;
                 }
 
                 if MAP.left(x, y).is_some() {
-                    Self::draw_cell_line(
+                    draw_cell_line(
                         display,
                         cell_point,
                         0,
                         SUB_CELL_SIZE as i32,
                         LineOrientation::Horizontal,
-                    );
+                    )?;
                 }
 
                 if MAP.right(x, y).is_some() {
-                    Self::draw_cell_line(
+                    draw_cell_line(
                         display,
                         cell_point,
                         SUB_CELL_SIZE as i32 + 1,
                         SUB_CELL_SIZE as i32,
                         LineOrientation::Horizontal,
-                    );
+                    )?;
                 }
 
                 // render center
@@ -438,10 +690,164 @@ impl TravelState {
                     cell_point + Point::new(SUB_CELL_SIZE as i32, SUB_CELL_SIZE as i32),
                     Size::new(1, 1),
                 )
-                .into_styled(gfx::WHITE)
-                .draw(display)
-                .unwrap();
+                .into_styled(PrimitiveStyle::with_fill(C::DIMMED))
+                .draw(display)?;
             }
         }
+
+        Ok(())
+    }
+
+    // counts how often the display gets called, that's what costs on the real target
+    struct Counting {
+        canvas: Canvas<BinaryColor>,
+        calls: usize,
+    }
+
+    impl Dimensions for Counting {
+        fn bounding_box(&self) -> Rectangle {
+            self.canvas.bounding_box()
+        }
+    }
+
+    impl DrawTarget for Counting {
+        type Color = BinaryColor;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            self.calls += 1;
+            self.canvas.draw_iter(pixels)
+        }
+    }
+
+    fn counting() -> Counting {
+        Counting {
+            canvas: Canvas::new(gfx::DISPLAY_WIDTH as u32, gfx::DISPLAY_HEIGHT as u32),
+            calls: 0,
+        }
+    }
+
+    // holds after every tick and press, whatever they were
+    pub fn check(state: &TravelState) {
+        assert!(
+            is_road(state.player),
+            "player off the road at {:?}",
+            state.player
+        );
+        assert!(is_road(state.goal), "goal off the road at {:?}", state.goal);
+        // once the bike gets there it's on to the lock, until there is a new goal
+        if state.transition.is_none() {
+            assert!(state.goal != state.player, "goal under the player");
+        }
+    }
+
+    #[test]
+    fn random_presses_stay_on_the_road() {
+        for entropy in 0..200 {
+            let mut random = Random::new(entropy);
+            let mut state = TravelState::new(&mut random);
+            for _ in 0..2000 {
+                match random.next_u32() % 8 {
+                    0 => state.button_up(),
+                    1 => state.button_down(),
+                    _ => state.tick(),
+                }
+                check(&state);
+                if state.transition.take().is_some() {
+                    state.set_random_goal(&mut random);
+                    check(&state);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_positions_are_on_the_road() {
+        let mut random = Random::new(1);
+        let mut state = TravelState::new(&mut random);
+        for _ in 0..10_000 {
+            state.set_random_player(&mut random);
+            state.set_random_goal(&mut random);
+            check(&state);
+        }
+    }
+
+    #[test]
+    fn drive_always_gets_somewhere() {
+        for (x, y) in (0..MAP_X).flat_map(|x| (0..MAP_Y).map(move |y| (x, y))) {
+            if !is_road((x, y)) {
+                continue;
+            }
+            for direction in DIRECTIONS {
+                // returns at all, and only ever to a neighbouring square
+                let ((to_x, to_y), _) = drive((x, y), direction);
+                assert!(is_road((to_x, to_y)));
+                assert!(to_x.abs_diff(x) + to_y.abs_diff(y) <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn cached_lane_follows_the_bike() {
+        let mut random = Random::new(5);
+        let mut state = TravelState::new(&mut random);
+        for tick in 0..5000 {
+            match random.next_u32() % 8 {
+                0 => state.button_up(),
+                1 => state.button_down(),
+                _ => (),
+            }
+            state.tick();
+            if state.transition.take().is_some() {
+                state.set_random_goal(&mut random);
+            }
+            assert_eq!(
+                state.lane_towards_goal(),
+                state.search_towards_goal(),
+                "tick {tick}"
+            );
+        }
+    }
+
+    #[test]
+    fn baked_map_looks_the_same() {
+        let mut random = Random::new(3);
+        for _ in 0..100 {
+            let state = TravelState::new(&mut random);
+            let (mut baked, mut cells) = (counting(), counting());
+            state.draw_small_screen(&mut baked).unwrap();
+            draw_map_by_cells(&state, &mut cells).unwrap();
+            assert_eq!(baked.canvas.pixels, cells.canvas.pixels);
+            assert_eq!(baked.canvas.outside, 0);
+        }
+    }
+
+    #[test]
+    fn baked_map_is_faster() {
+        const FRAMES: u32 = 2000;
+        let state = TravelState::new(Random::new(3));
+        let mut display = counting();
+
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            state.draw_small_screen(&mut display).unwrap();
+        }
+        let (baked, baked_calls) = (start.elapsed() / FRAMES, display.calls as u32 / FRAMES);
+
+        display.calls = 0;
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            draw_map_by_cells(&state, &mut display).unwrap();
+        }
+        let (cells, cells_calls) = (start.elapsed() / FRAMES, display.calls as u32 / FRAMES);
+
+        println!("baked: {baked:?} and {baked_calls} draw calls per frame");
+        println!("cells: {cells:?} and {cells_calls} draw calls per frame");
+        // the timing depends on the host and only means something with --release,
+        // the number of calls doesn't
+        assert!(baked_calls * 10 < cells_calls);
     }
 }
@@ -0,0 +1,79 @@
+use embedded_graphics::{draw_target::DrawTarget, prelude::*, primitives::Rectangle};
+
+// what a single display shows in place of both
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layout {
+    Big,
+    Small,
+    Both,
+}
+
+impl Layout {
+    pub const fn next(self) -> Self {
+        match self {
+            Layout::Big => Layout::Small,
+            Layout::Small => Layout::Both,
+            Layout::Both => Layout::Big,
+        }
+    }
+}
+
+pub const HALF_SCALE: u32 = 2;
+
+// both views at half size, side by side and vertically centered
+pub fn halves(size: Size) -> (Rectangle, Rectangle) {
+    let half = size / HALF_SCALE;
+    let top = (size.height / 4) as i32;
+    (
+        Rectangle::new(Point::new(0, top), half),
+        Rectangle::new(Point::new(half.width as i32, top), half),
+    )
+}
+
+// draws a full sized view into an area of the target, shrunk by `scale`
+// when several pixels land on the same spot the last one wins
+pub struct Viewport<'a, D> {
+    target: &'a mut D,
+    area: Rectangle,
+    scale: u32,
+}
+
+impl<'a, D: DrawTarget> Viewport<'a, D> {
+    pub const fn new(target: &'a mut D, area: Rectangle, scale: u32) -> Self {
+        Self {
+            target,
+            area,
+            scale,
+        }
+    }
+}
+
+impl<D: DrawTarget> Dimensions for Viewport<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(Point::zero(), self.area.size * self.scale)
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Viewport<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounds = self.bounding_box();
+        let area = self.area;
+        let scale = self.scale as i32;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(point, _)| bounds.contains(*point))
+                .map(|Pixel(point, color)| Pixel(area.top_left + point / scale, color)),
+        )
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(&self.area, color)
+    }
+}
@@ -0,0 +1,84 @@
+use crate::game::TICK_MS;
+use crate::idle::Power;
+use embedded_graphics::{draw_target::DrawTarget, pixelcolor::BinaryColor};
+
+// how often we look for a display that went missing
+const PROBE_TICKS: u8 = (1000 / TICK_MS) as u8;
+
+// the parts of a buffered display driver we need to manage it
+pub trait Panel: DrawTarget<Color = BinaryColor> {
+    // runs the init sequence, which fails if nobody answers on the bus
+    fn probe(&mut self) -> bool;
+    fn clear_frame(&mut self);
+    fn flush_frame(&mut self) -> bool;
+    // sends the frame in the background if the bus can, `finish_flush` waits for it
+    fn start_flush(&mut self) -> bool {
+        self.flush_frame()
+    }
+    fn finish_flush(&mut self) -> bool {
+        true
+    }
+    fn set_power(&mut self, power: Power);
+}
+
+pub struct Hotplug<P: Panel> {
+    panel: P,
+    present: bool,
+    probe_in: u8,
+    power: Power,
+}
+
+impl<P: Panel> Hotplug<P> {
+    pub fn new(mut panel: P) -> Self {
+        let present = panel.probe();
+        Self {
+            panel,
+            present,
+            probe_in: PROBE_TICKS,
+            power: Power::Awake,
+        }
+    }
+
+    pub const fn is_present(&self) -> bool {
+        self.present
+    }
+
+    pub fn set_power(&mut self, power: Power) {
+        self.power = power;
+        if self.present {
+            self.panel.set_power(power);
+        }
+    }
+
+    // periodically checks if a missing display is back
+    pub fn tick(&mut self) {
+        if self.present {
+            return;
+        }
+
+        self.probe_in = self.probe_in.saturating_sub(1);
+        if self.probe_in > 0 {
+            return;
+        }
+        self.probe_in = PROBE_TICKS;
+
+        self.present = self.panel.probe();
+        if self.present {
+            self.panel.set_power(self.power);
+        }
+    }
+
+    // draws the next frame while the last one is still going out, a failed flush marks it missing
+    pub fn render<F>(&mut self, draw: F)
+    where
+        F: FnOnce(&mut P) -> Result<(), P::Error>,
+    {
+        if !self.present {
+            return;
+        }
+
+        self.panel.clear_frame();
+        let drawn = draw(&mut self.panel).is_ok();
+        self.present = self.panel.finish_flush() && drawn && self.panel.start_flush();
+    }
+}
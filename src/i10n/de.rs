@@ -1,10 +1,21 @@
+pub const LOW_BATTERY: &str = "Akku fast leer";
+pub const BATTERY_EMPTY: &str = "Akku leer";
+pub const CODE: &str = "Code";
+pub const LAST_CODE: &str = "Letzte Runde";
+pub const REPLAY: &str = "Replay";
+pub const PAUSED: &str = "Pause";
+pub const DEMO: &str = "Demo";
+pub const RECOVERED: &str = "Nach Absturz neu gestartet";
+pub const REPLAY_MISMATCH: &str = "Replay ging schief";
+
 pub const INSTRUCTIONS: &[&[&str]] = &[
     &["Linke buttons fuer hoch/runter"],
     &["Obere Spur zum links abbiegen"],
     &["Untere Spur zum rechts abbiegen"],
     &[""],
     &["Roter button zum starten"],
-    &[""],
+    &["Hoch fuer einen Code"],
+    &["Runter fuer Wiederholungen"],
     &[
         "Sei schnell",
         "Sei schnell.",
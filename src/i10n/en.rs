@@ -1,9 +1,20 @@
+pub const LOW_BATTERY: &str = "Low battery";
+pub const BATTERY_EMPTY: &str = "Battery empty";
+pub const CODE: &str = "Code";
+pub const LAST_CODE: &str = "Last run";
+pub const REPLAY: &str = "Replay";
+pub const PAUSED: &str = "Paused";
+pub const DEMO: &str = "Demo";
+pub const RECOVERED: &str = "Recovered from crash";
+pub const REPLAY_MISMATCH: &str = "Replay went wrong";
+
 pub const INSTRUCTIONS: &[&[&str]] = &[
     &["Left buttons for up/down"],
     &["Upper lane to turn left"],
     &["Lower lane to turn right"],
     &[""],
     &["Red button to start game"],
-    &[""],
+    &["Up to enter a code"],
+    &["Down to watch replays"],
     &["Be quick", "Be quick.", "Be quick..", "Be quick..."],
 ];
@@ -0,0 +1,111 @@
+const fn seconds(secs: u64) -> u64 {
+    secs * 1_000_000
+}
+
+// timeouts in microseconds since the last button press
+pub struct IdleConfig {
+    pub dim_after: u64,
+    pub blank_after: u64,
+    pub sleep_after: u64,
+}
+
+pub const CONFIG: IdleConfig = IdleConfig {
+    dim_after: seconds(30),
+    blank_after: seconds(90),
+    sleep_after: seconds(300),
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Power {
+    Awake,
+    Dimmed,
+    Blank,
+    Sleep,
+}
+
+// goes by the timer, a frame takes longer than its nap
+pub struct Idle {
+    config: IdleConfig,
+    // timer readings in microseconds
+    now: u64,
+    last_activity: u64,
+}
+
+impl Idle {
+    pub const fn new(config: IdleConfig) -> Self {
+        Self {
+            config,
+            now: 0,
+            last_activity: 0,
+        }
+    }
+
+    pub const fn power(&self) -> Power {
+        let idle = self.now.saturating_sub(self.last_activity);
+        if idle >= self.config.sleep_after {
+            Power::Sleep
+        } else if idle >= self.config.blank_after {
+            Power::Blank
+        } else if idle >= self.config.dim_after {
+            Power::Dimmed
+        } else {
+            Power::Awake
+        }
+    }
+
+    // returns false if the input was only used to wake up
+    pub fn activity(&mut self) -> bool {
+        let awake = self.power() == Power::Awake;
+        self.last_activity = self.now;
+        awake
+    }
+
+    pub const fn tick(&mut self, now: u64) {
+        self.now = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dims_blanks_and_sleeps() {
+        let mut idle = Idle::new(CONFIG);
+        let start = seconds(1000);
+        idle.tick(start);
+        idle.activity();
+
+        for (secs, power) in [
+            (29, Power::Awake),
+            (30, Power::Dimmed),
+            (89, Power::Dimmed),
+            (90, Power::Blank),
+            (299, Power::Blank),
+            (300, Power::Sleep),
+        ] {
+            idle.tick(start + seconds(secs));
+            assert!(idle.power() == power, "after {secs}s");
+        }
+    }
+
+    #[test]
+    fn first_press_only_wakes_up() {
+        let mut idle = Idle::new(CONFIG);
+        idle.tick(seconds(45));
+        assert!(idle.power() == Power::Dimmed);
+        assert!(!idle.activity());
+        assert!(idle.power() == Power::Awake);
+        assert!(idle.activity());
+    }
+
+    #[test]
+    fn counts_time_not_ticks() {
+        let mut idle = Idle::new(CONFIG);
+        // slow frames, still dimmed after 30 seconds
+        for frame in 1..=400 {
+            idle.tick(frame * 75_000);
+        }
+        assert!(idle.power() == Power::Dimmed);
+    }
+}
@@ -0,0 +1,224 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// edges closer together than this are the contacts bouncing
+pub const DEBOUNCE_US: u32 = 20_000;
+const QUEUE_SIZE: usize = 16;
+const NUM_BUTTONS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Button {
+    Action,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy)]
+pub struct Press {
+    pub button: Button,
+    // microseconds since boot, wraps after about 71 minutes
+    pub at: u32,
+}
+
+// turns the raw edges of each button into presses
+pub struct Debounce {
+    last_edge: [Option<u32>; NUM_BUTTONS],
+}
+
+impl Debounce {
+    pub const fn new() -> Self {
+        Self {
+            last_edge: [None; NUM_BUTTONS],
+        }
+    }
+
+    // a press only counts if the button was quiet for a while before
+    pub fn edge(&mut self, button: Button, pressed: bool, at: u32) -> Option<Press> {
+        let last_edge = self.last_edge[button as usize].replace(at);
+        let quiet = last_edge.is_none_or(|last| at.wrapping_sub(last) >= DEBOUNCE_US);
+        (pressed && quiet).then_some(Press { button, at })
+    }
+}
+
+impl Default for Debounce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// filled by the gpio interrupt and drained by the main loop, without locking either of them
+pub struct Queue {
+    slots: [UnsafeCell<Option<Press>>; QUEUE_SIZE],
+    // only push moves the head, only pop moves the tail
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+// there is only ever one producer and one consumer
+unsafe impl Sync for Queue {}
+
+impl Queue {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { UnsafeCell::new(None) }; QUEUE_SIZE],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    // drops the press if the main loop fell too far behind
+    pub fn push(&self, press: Press) -> bool {
+        let head = self.head.load(Ordering::Relaxed);
+        if head.wrapping_sub(self.tail.load(Ordering::Acquire)) == QUEUE_SIZE {
+            return false;
+        }
+        unsafe {
+            *self.slots[head % QUEUE_SIZE].get() = Some(press);
+        }
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    pub fn pop(&self) -> Option<Press> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        let press = unsafe { *self.slots[tail % QUEUE_SIZE].get() };
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        press
+    }
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // feeds the level changes of one pin through the debouncer, like the interrupt does
+    fn edges(debounce: &mut Debounce, queue: &Queue, button: Button, edges: &[(u32, bool)]) {
+        for &(at, pressed) in edges {
+            if let Some(press) = debounce.edge(button, pressed, at) {
+                assert!(queue.push(press));
+            }
+        }
+    }
+
+    fn drain(queue: &Queue) -> Vec<(u8, u32)> {
+        core::iter::from_fn(|| queue.pop())
+            .map(|press| (press.button as u8, press.at))
+            .collect()
+    }
+
+    #[test]
+    fn bounces_are_one_press() {
+        let (mut debounce, queue) = (Debounce::new(), Queue::new());
+        // contacts bouncing on the way down and on the way up
+        edges(
+            &mut debounce,
+            &queue,
+            Button::Action,
+            &[
+                (1_000, true),
+                (1_200, false),
+                (1_500, true),
+                (1_900, false),
+                (2_100, true),
+                (80_000, false),
+                (80_300, true),
+                (80_600, false),
+            ],
+        );
+        assert_eq!(drain(&queue), [(Button::Action as u8, 1_000)]);
+
+        // tapping again within the debounce time still counts as the same press
+        edges(
+            &mut debounce,
+            &queue,
+            Button::Action,
+            &[
+                (200_000, true),
+                (200_000 + DEBOUNCE_US / 2, false),
+                (200_000 + DEBOUNCE_US - 1, true),
+                (300_000, false),
+            ],
+        );
+        assert_eq!(drain(&queue).len(), 1);
+    }
+
+    #[test]
+    fn buttons_bounce_on_their_own() {
+        let (mut debounce, queue) = (Debounce::new(), Queue::new());
+        edges(&mut debounce, &queue, Button::Up, &[(1_000, true)]);
+        edges(&mut debounce, &queue, Button::Down, &[(1_100, true)]);
+        assert_eq!(drain(&queue).len(), 2);
+    }
+
+    #[test]
+    fn survives_the_timer_wrapping() {
+        let (mut debounce, queue) = (Debounce::new(), Queue::new());
+        // the release right after the wrap is a bounce, the press much later isn't
+        edges(
+            &mut debounce,
+            &queue,
+            Button::Up,
+            &[
+                (u32::MAX - 5, true),
+                (10, false),
+                (12, true),
+                (u32::MAX / 2, true),
+            ],
+        );
+        assert_eq!(
+            drain(&queue),
+            [
+                (Button::Up as u8, u32::MAX - 5),
+                (Button::Up as u8, u32::MAX / 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn presses_come_out_in_order() {
+        let (mut debounce, queue) = (Debounce::new(), Queue::new());
+        edges(&mut debounce, &queue, Button::Up, &[(120_000, true)]);
+        edges(&mut debounce, &queue, Button::Action, &[(130_000, true)]);
+        edges(&mut debounce, &queue, Button::Down, &[(126_000, true)]);
+        assert_eq!(
+            drain(&queue),
+            [
+                (Button::Up as u8, 120_000),
+                (Button::Action as u8, 130_000),
+                (Button::Down as u8, 126_000)
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_presses_when_full() {
+        let queue = Queue::new();
+        let press = |at| Press {
+            button: Button::Down,
+            at,
+        };
+        for at in 0..QUEUE_SIZE as u32 {
+            assert!(queue.push(press(at)));
+        }
+        // the newest press is the one that gets lost
+        assert!(!queue.push(press(99)));
+        let ats: Vec<_> = drain(&queue).into_iter().map(|(_, at)| at).collect();
+        assert_eq!(ats, (0..QUEUE_SIZE as u32).collect::<Vec<_>>());
+
+        // and there is room again, all the way around the ring
+        for at in 0..QUEUE_SIZE as u32 * 3 {
+            assert!(queue.push(press(at)));
+            assert_eq!(queue.pop().map(|press| press.at), Some(at));
+        }
+        assert!(queue.pop().is_none());
+    }
+}
@@ -0,0 +1,19 @@
+#![warn(clippy::missing_const_for_fn)]
+#![cfg_attr(not(test), no_std)]
+
+// everything that doesn't touch the hardware, so it can be tested on the host
+pub mod autopilot;
+pub mod battery;
+pub mod game;
+pub mod gfx;
+pub mod hotplug;
+pub mod i10n;
+pub mod idle;
+pub mod input;
+pub mod light;
+pub mod oled;
+pub mod replay;
+pub mod rtttl;
+pub mod seed;
+pub mod sound;
+pub mod tunes;
@@ -0,0 +1,157 @@
+use crate::game::Event;
+use smart_leds_trait::RGB8;
+
+// the led is very bright, keep it dim
+const MAX_BRIGHTNESS: u8 = 32;
+
+const PULSE_PERIOD: u8 = 40;
+const FLASH_TICKS: u8 = 4;
+const RAINBOW_TICKS: u8 = 24;
+const RAINBOW_STEP: u8 = 11;
+
+const OFF: RGB8 = RGB8::new(0, 0, 0);
+const GREEN: RGB8 = RGB8::new(0, MAX_BRIGHTNESS, 0);
+const RED: RGB8 = RGB8::new(MAX_BRIGHTNESS, 0, 0);
+
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    Off,
+    Pulse,
+    Flash(RGB8),
+    Rainbow,
+}
+
+impl Pattern {
+    pub const fn color(&self, tick: u8) -> RGB8 {
+        match self {
+            Pattern::Off => OFF,
+            Pattern::Pulse => pulse(tick),
+            // on for the first half, off for the second
+            Pattern::Flash(color) => {
+                if tick % FLASH_TICKS < FLASH_TICKS / 2 {
+                    *color
+                } else {
+                    OFF
+                }
+            }
+            Pattern::Rainbow => wheel(tick.wrapping_mul(RAINBOW_STEP)),
+        }
+    }
+}
+
+// triangle wave of a dim white
+pub const fn pulse(tick: u8) -> RGB8 {
+    let half = PULSE_PERIOD / 2;
+    let step = tick % PULSE_PERIOD;
+    let step = if step < half {
+        step
+    } else {
+        PULSE_PERIOD - step
+    };
+    let level = (step as u16 * MAX_BRIGHTNESS as u16 / half as u16) as u8;
+    RGB8::new(level, level, level)
+}
+
+// maps 0..=255 onto red -> green -> blue -> red
+pub const fn wheel(pos: u8) -> RGB8 {
+    let third = (pos % 85) as u16 * 3;
+    let rising = (third * MAX_BRIGHTNESS as u16 / 255) as u8;
+    let falling = MAX_BRIGHTNESS - rising;
+    match pos / 85 {
+        0 => RGB8::new(falling, rising, 0),
+        1 => RGB8::new(0, falling, rising),
+        _ => RGB8::new(rising, 0, falling),
+    }
+}
+
+pub struct Light {
+    ambient: Pattern,
+    effect: Option<(Pattern, u8)>,
+    tick: u8,
+}
+
+impl Light {
+    pub const fn new(ambient: Pattern) -> Self {
+        Self {
+            ambient,
+            effect: None,
+            tick: 0,
+        }
+    }
+
+    // the pattern shown while no effect is running
+    pub const fn ambient(&mut self, pattern: Pattern) {
+        self.ambient = pattern;
+    }
+
+    pub const fn event(&mut self, event: Event) {
+        self.effect = match event {
+            Event::PinSet => Some((Pattern::Flash(GREEN), FLASH_TICKS)),
+            Event::PinSlipped => Some((Pattern::Flash(RED), FLASH_TICKS)),
+            Event::LockOpened => Some((Pattern::Rainbow, RAINBOW_TICKS)),
+            _ => return,
+        };
+        self.tick = 0;
+    }
+
+    pub const fn tick(&mut self) -> RGB8 {
+        let pattern = match &mut self.effect {
+            Some((pattern, remaining)) if *remaining > 0 => {
+                *remaining -= 1;
+                *pattern
+            }
+            _ => {
+                self.effect = None;
+                self.ambient
+            }
+        };
+        let color = pattern.color(self.tick);
+        self.tick = self.tick.wrapping_add(1);
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulse_rises_and_falls() {
+        let half = PULSE_PERIOD / 2;
+        assert_eq!(pulse(0), OFF);
+        assert_eq!(pulse(half), RGB8::new(32, 32, 32));
+        for tick in 0..PULSE_PERIOD {
+            assert_eq!(pulse(tick), pulse(PULSE_PERIOD - tick));
+            assert_eq!(pulse(tick), pulse(tick + PULSE_PERIOD));
+        }
+        for tick in 0..half {
+            assert!(pulse(tick).r < pulse(tick + 1).r);
+        }
+    }
+
+    #[test]
+    fn wheel_goes_around() {
+        assert_eq!(wheel(0), RED);
+        assert_eq!(wheel(85), GREEN);
+        assert_eq!(wheel(170), RGB8::new(0, 0, MAX_BRIGHTNESS));
+        for pos in 0..=u8::MAX {
+            let color = wheel(pos);
+            // never brighter than allowed, never all dark
+            assert!(color.r.max(color.g).max(color.b) <= MAX_BRIGHTNESS);
+            let total = color.r as u16 + color.g as u16 + color.b as u16;
+            assert!(total >= MAX_BRIGHTNESS as u16 - 1, "{pos}: {color:?}");
+        }
+    }
+
+    #[test]
+    fn effect_returns_to_ambient() {
+        let mut light = Light::new(Pattern::Off);
+        light.event(Event::PinSlipped);
+        let colors: Vec<_> = (0..6).map(|_| light.tick()).collect();
+        assert_eq!(colors, [RED, RED, OFF, OFF, OFF, OFF]);
+
+        // a turn has no light effect
+        light.event(Event::Turn);
+        assert_eq!(light.tick(), OFF);
+    }
+}
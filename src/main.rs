@@ -3,37 +3,67 @@
 #![no_main]
 
 mod big;
-mod game;
-mod gfx;
-mod i10n;
+mod board;
+mod buttons;
+mod buzzer;
+mod core1;
+// only the i2c boards stream frames by dma
+#[cfg(any(feature = "rp2040-zero", feature = "pico"))]
+mod dma;
+mod flash;
+mod panic;
+mod sleep;
 mod small;
 
-use crate::game::Game;
+use game_taco_burglar::{
+    battery, game, gfx, hotplug, i10n, idle, input, light, oled, replay, sound, tunes,
+};
+
+use crate::battery::{Battery, Level};
+use crate::board::{entry, hal, Buses, Pins, XOSC_CRYSTAL_FREQ};
+use crate::core1::{Core1, Snapshot};
+use crate::game::{Game, Screen};
+use crate::gfx::burnin::Drift;
+use crate::gfx::viewport::Layout;
+use crate::hotplug::Hotplug;
+use crate::idle::{Idle, Power};
+use crate::input::Button;
+use crate::light::{Light, Pattern};
+use crate::sound::Sequencer;
+use core::iter;
 use defmt_rtt as _;
-use eh0::timer::CountDown;
-use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
-use embedded_hal::digital::InputPin;
+use eh0::adc::OneShot;
 use fugit::ExtU32;
-use fugit::RateExtU32;
-use panic_halt as _;
-use waveshare_rp2040_zero::entry;
-use waveshare_rp2040_zero::{
-    hal::{
-        clocks::{init_clocks_and_plls, Clock},
-        i2c::I2C,
-        pac,
-        rosc::RingOscillator,
-        timer::Timer,
-        watchdog::Watchdog,
-        Sio,
-    },
-    Pins, XOSC_CRYSTAL_FREQ,
+use hal::{
+    adc::{Adc, AdcPin},
+    clocks::{init_clocks_and_plls, Clock},
+    dma::DMAExt,
+    pac,
+    pio::PIOExt,
+    pwm::Slices,
+    rosc::RingOscillator,
+    timer::{Alarm, Timer},
+    watchdog::Watchdog,
+    Sio,
 };
+use rand_core::RngCore;
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+use ws2812_pio::Ws2812Direct;
+
+const LOW_BATTERY_NOTICE_TICKS: u8 = 60;
+const RECOVERED_NOTICE_TICKS: u8 = 60;
+const WATCHDOG_TIMEOUT_US: u32 = 1_000_000;
+const SHUTDOWN_TICKS: u8 = 60;
 
 #[entry]
 fn main() -> ! {
     let mut pac = pac::Peripherals::take().unwrap();
 
+    // check if the watchdog had to reboot us
+    let crashed = pac.WATCHDOG.reason().read().timer().bit_is_set();
+
+    flash::check_layout();
+
     // Configure clocks and timers
     let mut watchdog = Watchdog::new(pac.WATCHDOG);
     let clocks = init_clocks_and_plls(
@@ -48,8 +78,13 @@ fn main() -> ! {
     .ok()
     .unwrap();
 
-    let timer = Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
-    let mut delay = timer.count_down();
+    // sleep between frames until the alarm goes off
+    let mut timer = Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_0().unwrap();
+    alarm.enable_interrupt();
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::TIMER_IRQ_0);
+    }
     let mut rosc = RingOscillator::new(pac.ROSC).initialize();
 
     // Configure gpio
@@ -60,88 +95,205 @@ fn main() -> ! {
         sio.gpio_bank0,
         &mut pac.RESETS,
     );
+    let board = board::split(pins);
 
-    // configure button
-    let mut action_in_pin = pins.gp8.into_pull_up_input();
-    let mut up_in_pin = pins.gp27.into_pull_up_input();
-    let mut down_in_pin = pins.gp15.into_pull_up_input();
-
-    // setup i2c
-    let small_i2c = I2C::i2c0(
-        pac.I2C0,
-        pins.gp12.into_pull_type().into_function(), // sda
-        pins.gp13.into_pull_type().into_function(), // scl
-        400.kHz(),
-        &mut pac.RESETS,
-        clocks.peripheral_clock.freq(),
+    // configure buttons, presses are queued up by the gpio interrupt
+    buttons::init(board.action, board.up, board.down);
+
+    // configure buzzer
+    let pwm_slices = Slices::new(pac.PWM, &mut pac.RESETS);
+    let buzzer = buzzer::init(
+        board::buzzer_pwm(pwm_slices),
+        board.buzzer,
+        clocks.system_clock.freq(),
     );
-    let big_i2c = I2C::i2c1(
-        pac.I2C1,
-        pins.gp10.into_pull_type().into_function(), // sda
-        pins.gp11.into_pull_type().into_function(), // scl
-        400.kHz(),
+    let mut sound = Sequencer::new(buzzer);
+
+    // configure battery monitor
+    let mut adc = Adc::new(pac.ADC, &mut pac.RESETS);
+    let mut battery_pin = AdcPin::new(board.battery).unwrap();
+    let mut battery = Battery::new(board::BATTERY_DIVIDER);
+
+    // configure onboard rgb led
+    let (mut pio, sm0, _, _, _) = pac.PIO0.split(&mut pac.RESETS);
+    let mut led = Ws2812Direct::new(board.led, &mut pio, sm0, clocks.peripheral_clock.freq());
+    let mut light = Light::new(Pattern::Pulse);
+
+    // setup display buses
+    let (small_interface, big_interface) = board::displays(
+        board.displays,
+        Buses {
+            i2c0: pac.I2C0,
+            #[cfg(any(feature = "rp2040-zero", feature = "pico"))]
+            i2c1: pac.I2C1,
+            #[cfg(feature = "custom")]
+            spi1: pac.SPI1,
+        },
         &mut pac.RESETS,
         clocks.peripheral_clock.freq(),
     );
 
-    // init screens
-    let mut small_display = small::init(small_i2c);
-    let mut big_display = big::init(big_i2c);
+    // init screens, either of them may be missing
+    let small_display = Hotplug::new(small::init(small_interface));
+    let dma_channels = pac.DMA.split(&mut pac.RESETS);
+    let mut big_display = Hotplug::new(big::init(board::big_dma(big_interface, dma_channels.ch0)));
+
+    // the second core takes care of the small screen
+    let mut core1 = Core1::spawn(&mut pac.PSM, &mut pac.PPB, sio.fifo, small_display);
 
     // game state
-    let mut last_state = false;
+    let mut last_chord = false;
+    let mut idle = Idle::new(idle::CONFIG);
+    let mut applied_power = Power::Awake;
+    let mut drift = Drift::new();
+    let mut battery_level = Level::Ok;
+    let mut shutdown: Option<u8> = None;
+    let mut layout = Layout::Both;
 
     // enter loop
-    let mut game = Game::new(&mut rosc);
+    // the ring oscillator is slow, it only seeds the codes of the runs
+    let mut game = Game::new(rosc.next_u32());
+    if crashed {
+        game.show_notice(i10n::RECOVERED, RECOVERED_NOTICE_TICKS);
+    }
+    watchdog.start(WATCHDOG_TIMEOUT_US.micros());
     loop {
-        while action_in_pin.is_low().unwrap() {
-            if !last_state {
-                game.button_action();
-                last_state = true;
-            }
-            delay.start(50.millis());
-            let _ = nb::block!(delay.wait());
-        }
-        last_state = false;
+        watchdog.feed();
 
-        while up_in_pin.is_low().unwrap() {
-            if !last_state {
-                game.button_up();
-                last_state = true;
-            }
-            delay.start(50.millis());
-            let _ = nb::block!(delay.wait());
+        // holding up and down together switches what a single screen shows
+        let chord = buttons::is_held(Button::Up) && buttons::is_held(Button::Down);
+        if chord && !last_chord && idle.activity() {
+            layout = layout.next();
         }
-        last_state = false;
+        last_chord = chord;
 
-        while down_in_pin.is_low().unwrap() {
-            if !last_state {
-                game.button_down();
-                last_state = true;
+        // handle every press since the last frame, even the short ones
+        while let Some(press) = buttons::PRESSES.pop() {
+            // any button ends the demo and does nothing else
+            let awake = idle.activity();
+            if game.stop_demo() || !awake {
+                continue;
+            }
+            match press.button {
+                Button::Action => game.button_action(),
+                Button::Up | Button::Down if chord => (),
+                Button::Up => game.button_up(),
+                Button::Down => game.button_down(),
             }
-            delay.start(50.millis());
-            let _ = nb::block!(delay.wait());
         }
-        last_state = false;
 
-        // clear screens
-        small_display.clear(BinaryColor::Off).unwrap();
-        big_display.clear();
+        // look for a missing big screen
+        big_display.tick();
 
-        // render small screen
-        game.draw_small_screen(&mut small_display);
-        small_display.flush().unwrap();
+        // render small screen on the other core, or both views if the big one is missing
+        core1.publish(Snapshot {
+            view: game.view().clone(),
+            drift,
+            single: (!big_display.is_present()).then_some(layout),
+        });
 
-        // render big screen
-        game.draw_big_screen(&mut big_display);
-        big_display.flush().unwrap();
+        // render big screen, the frame goes out while we tick the game, both views if the small
+        // one is missing
+        let single = !core1.is_present();
+        big_display.render(|display| {
+            let display = &mut drift.apply(display);
+            if single {
+                game.view().draw_single_screen(display, layout)
+            } else {
+                game.view().draw_big_screen(display)
+            }
+        });
 
         // sleep for frame rate
-        delay.start(50.millis());
-        let _ = nb::block!(delay.wait());
+        sleep::nap(&mut alarm, game::TICK_MS.millis());
 
         // process the concept of tick
         game.tick();
         game.transition();
+        drift.tick();
+
+        // keep finished runs around for replays
+        if let Some(recording) = game.take_recording() {
+            core1.parked(|| flash::save(recording));
+        }
+        if let Some(index) = game.take_replay_request() {
+            match flash::recording(index) {
+                Some(recording) => game.play_replay(recording, index),
+                None => game.stop_replay(),
+            }
+        }
+
+        // play sound and light effects
+        while let Some(event) = game.take_event() {
+            sound.event(event);
+            light.event(event);
+        }
+
+        // a run left alone dims and sleeps, but nobody presses anything while watching a replay
+        idle.tick(timer.get_counter().ticks());
+        if game.is_replay() {
+            idle.activity();
+        }
+        let power = idle.power();
+        let on_start = matches!(game.screen(), Screen::Start) || game.is_demo();
+        let awake = on_start && power == Power::Awake;
+
+        sound.music(awake.then_some(&tunes::START[..]));
+        sound.tick();
+
+        light.ambient(if awake { Pattern::Pulse } else { Pattern::Off });
+        led.write(iter::once(light.tick())).ok();
+
+        // check the battery
+        // the adc can't fail
+        let Ok(raw) = nb::block!(OneShot::<_, u16, _>::read(&mut adc, &mut battery_pin));
+        battery.sample(raw);
+        // without a battery the gauge stays full
+        if battery.level() != Level::Missing {
+            game.set_battery(battery.percent());
+        }
+        match battery.level() {
+            // give the notice some time before turning off
+            Level::Critical if shutdown.is_none() => {
+                game.show_notice(i10n::BATTERY_EMPTY, u8::MAX);
+                shutdown = Some(SHUTDOWN_TICKS);
+            }
+            Level::Low if battery_level == Level::Ok => {
+                game.show_notice(i10n::LOW_BATTERY, LOW_BATTERY_NOTICE_TICKS);
+            }
+            _ => (),
+        }
+        battery_level = battery.level();
+        let power = if let Some(ticks) = &mut shutdown {
+            *ticks = ticks.saturating_sub(1);
+            if *ticks == 0 {
+                Power::Sleep
+            } else {
+                Power::Awake
+            }
+        } else {
+            power
+        };
+
+        // dim or blank the screens
+        if power != applied_power {
+            core1.set_power(power);
+            big_display.set_power(power);
+            applied_power = power;
+        }
+
+        if power == Power::Sleep {
+            // the run ends here as far as the replay is concerned
+            game.finish_recording();
+            if let Some(recording) = game.take_recording() {
+                core1.parked(|| flash::save(recording));
+            }
+
+            // make sure the small screen is off before the clocks stop
+            core1.wait();
+            led.write(iter::once(RGB8::default())).ok();
+            buttons::enable_dormant_wake();
+            sleep::dormant();
+        }
     }
 }
@@ -0,0 +1,470 @@
+use crate::gfx::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::hotplug::Panel;
+use crate::idle::Power;
+use core::convert::Infallible;
+use display_interface::{DataFormat, WriteOnlyDataCommand};
+use display_interface_i2c::I2CInterface;
+use display_interface_spi::SPIInterface;
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use embedded_hal::{digital::OutputPin, i2c::I2c, spi::SpiDevice};
+
+// all the controllers we support are page addressed, 8 pixels per byte
+const WIDTH: usize = DISPLAY_WIDTH as usize;
+const HEIGHT: usize = DISPLAY_HEIGHT as usize;
+pub const BUFFER_SIZE: usize = WIDTH * HEIGHT / 8;
+
+const I2C_ADDRESS: u8 = 0x3c;
+pub const I2C_COMMAND_BYTE: u8 = 0x00;
+pub const I2C_DATA_BYTE: u8 = 0x40;
+
+const CONTRAST_DIMMED: u8 = 0x01;
+
+const CMD_CONTRAST: u8 = 0x81;
+const CMD_DISPLAY_OFF: u8 = 0xae;
+const CMD_DISPLAY_ON: u8 = 0xaf;
+const CMD_PAGE: u8 = 0xb0;
+const CMD_COLUMN_LOW: u8 = 0x00;
+const CMD_COLUMN_HIGH: u8 = 0x10;
+
+#[derive(Clone, Copy)]
+pub enum Rotation {
+    Normal,
+    UpsideDown,
+}
+
+pub struct Controller {
+    // everything up to turning the display on
+    init: &'static [u8],
+    // segment remap and com scan direction for each rotation
+    normal: [u8; 2],
+    upside_down: [u8; 2],
+    contrast: u8,
+    // first visible column in the ram of the controller
+    column_offset: u8,
+    // the pages run along x instead of y, like a portrait panel used sideways
+    transposed: bool,
+}
+
+pub const SSD1306: Controller = Controller {
+    init: &[
+        0xd5, 0x80, // clock
+        0xa8, 0x3f, // 64 rows
+        0xd3, 0x00, // no offset
+        0x40, // start line
+        0x8d, 0x14, // charge pump
+        0x20, 0x02, // page addressing
+        0xda, 0x12, // com pins
+        0xd9, 0xf1, // precharge
+        0xdb, 0x40, // vcomh
+        0x2e, // no scrolling
+        0xa4, // show ram
+        0xa6, // not inverted
+    ],
+    normal: [0xa1, 0xc8],
+    upside_down: [0xa0, 0xc0],
+    contrast: 0xcf,
+    column_offset: 0,
+    transposed: false,
+};
+
+// like the ssd1306, but without a charge pump
+pub const SSD1309: Controller = Controller {
+    init: &[
+        0xd5, 0xa0, // clock
+        0xa8, 0x3f, // 64 rows
+        0xd3, 0x00, // no offset
+        0x40, // start line
+        0x20, 0x02, // page addressing
+        0xda, 0x12, // com pins
+        0xd9, 0xf1, // precharge
+        0xdb, 0x34, // vcomh
+        0x2e, // no scrolling
+        0xa4, // show ram
+        0xa6, // not inverted
+    ],
+    normal: [0xa1, 0xc8],
+    upside_down: [0xa0, 0xc0],
+    contrast: 0x80,
+    column_offset: 0,
+    transposed: false,
+};
+
+// 132 columns of ram, the panel sits in the middle
+pub const SH1106: Controller = Controller {
+    init: &[
+        0xd5, 0x80, // clock
+        0xa8, 0x3f, // 64 rows
+        0xd3, 0x00, // no offset
+        0x40, // start line
+        0xad, 0x8b, // dc-dc converter
+        0xda, 0x12, // com pins
+        0xd9, 0x1f, // precharge
+        0xdb, 0x40, // vcom
+        0x32, // pump voltage
+        0xa4, // show ram
+        0xa6, // not inverted
+    ],
+    normal: [0xa1, 0xc8],
+    upside_down: [0xa0, 0xc0],
+    contrast: 0x80,
+    column_offset: 2,
+    transposed: false,
+};
+
+// 64x128 modules, used sideways
+pub const SH1107: Controller = Controller {
+    init: &[
+        0xd5, 0x41, // clock
+        0xa8, 0x7f, // all 128 coms
+        0xd3, 0x60, // offset into the 128 rows of ram
+        0xdc, 0x00, // start line
+        0x20, // page addressing
+        0xad, 0x8a, // dc-dc converter
+        0xd9, 0x22, // precharge
+        0xdb, 0x35, // vcom
+        0xa4, // show ram
+        0xa6, // not inverted
+    ],
+    normal: [0xa0, 0xc0],
+    upside_down: [0xa1, 0xc8],
+    contrast: 0x6f,
+    column_offset: 0,
+    transposed: true,
+};
+
+pub fn i2c<I: I2c>(i2c: I) -> I2CInterface<I> {
+    I2CInterface::new(i2c, I2C_ADDRESS, I2C_DATA_BYTE)
+}
+
+// what we need from the bus, sending a frame in the background is optional
+pub trait Interface: WriteOnlyDataCommand {
+    fn can_queue(&self) -> bool {
+        false
+    }
+
+    // adds a command or data transfer to the next frame
+    fn queue(&mut self, _bytes: &[u8], _data: bool) {}
+
+    // starts sending everything queued so far
+    fn send_queued(&mut self) {}
+
+    // waits for the frame to go out, false if it didn't make it to the display
+    fn wait_sent(&mut self) -> bool {
+        true
+    }
+}
+
+impl<I: I2c> Interface for I2CInterface<I> {}
+
+impl<SPI: SpiDevice, DC: OutputPin> Interface for SPIInterface<SPI, DC> {}
+
+// a frame buffer for any of the controllers above, on any bus
+pub struct Oled<DI> {
+    interface: DI,
+    controller: &'static Controller,
+    rotation: Rotation,
+    buffer: [u8; BUFFER_SIZE],
+    // what the display currently shows, so we only send what changed
+    sent: [u8; BUFFER_SIZE],
+    in_sync: bool,
+}
+
+impl<DI: Interface> Oled<DI> {
+    pub const fn new(interface: DI, controller: &'static Controller, rotation: Rotation) -> Self {
+        Self {
+            interface,
+            controller,
+            rotation,
+            buffer: [0; BUFFER_SIZE],
+            sent: [0; BUFFER_SIZE],
+            in_sync: false,
+        }
+    }
+
+    fn command(&mut self, bytes: &[u8]) -> bool {
+        self.interface.send_commands(DataFormat::U8(bytes)).is_ok()
+    }
+
+    const fn page_size(&self) -> usize {
+        if self.controller.transposed {
+            HEIGHT
+        } else {
+            WIDTH
+        }
+    }
+
+    const fn set_pixel(&mut self, point: Point, on: bool) {
+        let (x, y) = (point.x as usize, point.y as usize);
+        let (index, bit) = if self.controller.transposed {
+            ((x / 8) * HEIGHT + y, x % 8)
+        } else {
+            ((y / 8) * WIDTH + x, y % 8)
+        };
+        if on {
+            self.buffer[index] |= 1 << bit;
+        } else {
+            self.buffer[index] &= !(1 << bit);
+        }
+    }
+}
+
+impl<DI> OriginDimensions for Oled<DI> {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl<DI: Interface> DrawTarget for Oled<DI> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.bounding_box();
+        for Pixel(point, color) in pixels {
+            if area.contains(point) {
+                self.set_pixel(point, color.is_on());
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.buffer.fill(if color.is_on() { 0xff } else { 0x00 });
+        Ok(())
+    }
+}
+
+impl<DI: Interface> Panel for Oled<DI> {
+    fn probe(&mut self) -> bool {
+        // the display ram is garbage after init
+        self.in_sync = false;
+        let rotation = match self.rotation {
+            Rotation::Normal => self.controller.normal,
+            Rotation::UpsideDown => self.controller.upside_down,
+        };
+        self.command(&[CMD_DISPLAY_OFF])
+            && self.command(self.controller.init)
+            && self.command(&rotation)
+            && self.command(&[CMD_CONTRAST, self.controller.contrast, CMD_DISPLAY_ON])
+    }
+
+    fn clear_frame(&mut self) {
+        self.buffer.fill(0);
+    }
+
+    fn flush_frame(&mut self) -> bool {
+        self.start_flush() && self.finish_flush()
+    }
+
+    fn start_flush(&mut self) -> bool {
+        let queued = self.interface.can_queue();
+        let page_size = self.page_size();
+        for page in 0..BUFFER_SIZE / page_size {
+            let range = page * page_size..(page + 1) * page_size;
+
+            // only send the columns between the first and last change
+            let (first, last) = if self.in_sync {
+                let new = &self.buffer[range.clone()];
+                let old = &self.sent[range.clone()];
+                let changed = |(_, (a, b)): &(usize, (&u8, &u8))| a != b;
+                let Some((first, _)) = new.iter().zip(old).enumerate().find(changed) else {
+                    continue;
+                };
+                let last = new.iter().zip(old).enumerate().rfind(changed);
+                (first, last.map_or(first, |(last, _)| last))
+            } else {
+                (0, page_size - 1)
+            };
+
+            let column = self.controller.column_offset + first as u8;
+            let commands = [
+                CMD_PAGE | page as u8,
+                CMD_COLUMN_LOW | (column & 0x0f),
+                CMD_COLUMN_HIGH | (column >> 4),
+            ];
+            let data = range.start + first..=range.start + last;
+            if queued {
+                self.interface.queue(&commands, false);
+                self.interface.queue(&self.buffer[data], true);
+            } else if !self.command(&commands)
+                || self
+                    .interface
+                    .send_data(DataFormat::U8(&self.buffer[data]))
+                    .is_err()
+            {
+                self.in_sync = false;
+                return false;
+            }
+        }
+
+        if queued {
+            self.interface.send_queued();
+        }
+        self.sent = self.buffer;
+        self.in_sync = true;
+        true
+    }
+
+    fn finish_flush(&mut self) -> bool {
+        if !self.interface.wait_sent() {
+            self.in_sync = false;
+            return false;
+        }
+        true
+    }
+
+    fn set_power(&mut self, power: Power) {
+        match power {
+            Power::Awake => {
+                self.command(&[CMD_CONTRAST, self.controller.contrast, CMD_DISPLAY_ON]);
+            }
+            Power::Dimmed => {
+                self.command(&[CMD_CONTRAST, CONTRAST_DIMMED, CMD_DISPLAY_ON]);
+            }
+            Power::Blank | Power::Sleep => {
+                self.command(&[CMD_DISPLAY_OFF]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot;
+    use crate::game::{Game, Screen};
+    use crate::input::Button;
+    use display_interface::DisplayError;
+
+    // enough ram for the sh1106 and the sh1107
+    const RAM_COLUMNS: usize = 132;
+    const RAM_PAGES: usize = 16;
+
+    // a page addressed display that counts the bytes it gets
+    struct Ram {
+        ram: [[u8; RAM_COLUMNS]; RAM_PAGES],
+        page: usize,
+        column: usize,
+        bytes: usize,
+    }
+
+    impl Ram {
+        const fn new() -> Self {
+            Self {
+                ram: [[0; RAM_COLUMNS]; RAM_PAGES],
+                page: 0,
+                column: 0,
+                bytes: 0,
+            }
+        }
+    }
+
+    impl WriteOnlyDataCommand for Ram {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            let DataFormat::U8(bytes) = cmd else {
+                return Err(DisplayError::DataFormatNotImplemented);
+            };
+            self.bytes += bytes.len();
+            if let [page, low, high] = *bytes {
+                if page & 0xf0 == CMD_PAGE {
+                    self.page = (page & 0x0f) as usize;
+                    self.column = (low & 0x0f | high << 4) as usize;
+                }
+            }
+            Ok(())
+        }
+
+        fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            let DataFormat::U8(bytes) = buf else {
+                return Err(DisplayError::DataFormatNotImplemented);
+            };
+            self.bytes += bytes.len();
+            for &byte in bytes {
+                self.ram[self.page][self.column] = byte;
+                self.column += 1;
+            }
+            Ok(())
+        }
+    }
+
+    impl Interface for Ram {}
+
+    fn probed(controller: &'static Controller) -> Oled<Ram> {
+        let mut oled = Oled::new(Ram::new(), controller, Rotation::Normal);
+        assert!(oled.probe());
+        oled
+    }
+
+    // what sending the whole buffer costs
+    fn full_frame(controller: &'static Controller) -> usize {
+        let pages = BUFFER_SIZE / probed(controller).page_size();
+        BUFFER_SIZE + pages * 3
+    }
+
+    #[derive(Default)]
+    struct Traffic {
+        frames: usize,
+        bytes: usize,
+    }
+
+    #[test]
+    fn only_sends_what_changed() {
+        for (chip, controller) in [
+            ("ssd1306", &SSD1306),
+            ("ssd1309", &SSD1309),
+            ("sh1106", &SH1106),
+            ("sh1107", &SH1107),
+        ] {
+            let full = full_frame(controller);
+            let mut big = probed(controller);
+            let mut small = probed(controller);
+            let mut game = Game::new(11);
+            // start, travel and lock
+            let mut traffic: [Traffic; 3] = Default::default();
+
+            for frame in 0..2000 {
+                match game.view().screen() {
+                    Screen::Start if frame % 100 == 99 => game.button_action(),
+                    _ => match autopilot::press(game.view()) {
+                        Some(Button::Action) => game.button_action(),
+                        Some(Button::Up) => game.button_up(),
+                        Some(Button::Down) => game.button_down(),
+                        None => (),
+                    },
+                }
+                game.tick();
+                game.transition();
+
+                let scene = game.view().screen() as usize;
+                for (oled, small_view) in [(&mut big, false), (&mut small, true)] {
+                    let before = oled.interface.bytes;
+                    oled.clear_frame();
+                    if small_view {
+                        game.view().draw_small_screen(oled).unwrap();
+                    } else {
+                        game.view().draw_big_screen(oled).unwrap();
+                    }
+                    assert!(oled.flush_frame());
+                    traffic[scene].bytes += oled.interface.bytes - before;
+
+                    // the display ends up with the same picture as a full flush
+                    let mut check = probed(controller);
+                    check.buffer = oled.buffer;
+                    assert!(check.flush_frame());
+                    assert_eq!(oled.interface.ram, check.interface.ram);
+                }
+                traffic[scene].frames += 2;
+            }
+
+            for (name, traffic) in ["start", "travel", "lock"].iter().zip(&traffic) {
+                assert!(traffic.frames > 0, "never saw {name}");
+                let per_frame = traffic.bytes / traffic.frames;
+                println!("{chip} {name}: {per_frame} of {full} bytes per frame");
+                assert!(per_frame < full / 2, "{name} sends {per_frame} bytes");
+            }
+        }
+    }
+}
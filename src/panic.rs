@@ -0,0 +1,149 @@
+use crate::board::{self, hal, Buses, Pins};
+use crate::gfx::{self, Palette};
+use crate::hotplug::Panel;
+use crate::{big, small};
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::str;
+use embedded_graphics::{
+    prelude::*,
+    text::{Baseline, Text},
+};
+use fugit::{ExtU32, RateExtU32};
+use hal::{pac, watchdog::Watchdog, Sio};
+
+// keep the panic on screen for a while before the watchdog reboots us
+const PANIC_SCREEN_US: u32 = 5_000_000;
+// this is what init_clocks_and_plls configures
+const PERIPHERAL_CLOCK_MHZ: u32 = 125;
+
+const LINE_LENGTH: usize = gfx::DISPLAY_WIDTH as usize / gfx::CHAR_WIDTH;
+const MESSAGE_LINES: usize = 6;
+
+// formats into a fixed buffer, cutting off whatever doesn't fit
+struct Buffer {
+    buf: [u8; LINE_LENGTH * MESSAGE_LINES],
+    len: usize,
+}
+
+impl Buffer {
+    fn as_str(&self) -> &str {
+        // only ever cut off between characters
+        str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let remaining = self.buf.len() - self.len;
+        let mut end = s.len().min(remaining);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        let bytes = &s.as_bytes()[..end];
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+}
+
+fn draw<C: Palette, D: DrawTarget<Color = C>>(display: &mut D, info: &PanicInfo, message: &Buffer) {
+    display.clear(C::BACKGROUND).ok();
+
+    let line_height = gfx::TEXT_FONT.character_size.height as i32 + 1;
+    let mut line = |num: i32, text: &str| {
+        Text::with_baseline(
+            text,
+            Point::new(0, num * line_height),
+            gfx::text_style(),
+            Baseline::Top,
+        )
+        .draw(display)
+        .ok();
+    };
+
+    line(0, "panic");
+    if let Some(location) = info.location() {
+        line(1, location.file());
+        let mut buf = itoa::Buffer::new();
+        line(2, buf.format(location.line()));
+    }
+
+    // wrap by characters, not bytes
+    let mut rest = message.as_str();
+    let mut num = 3;
+    while !rest.is_empty() {
+        let end = rest
+            .char_indices()
+            .nth(LINE_LENGTH)
+            .map_or(rest.len(), |(index, _)| index);
+        let (text, next) = rest.split_at(end);
+        line(num, text);
+        rest = next;
+        num += 1;
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    // we're not going to return to whoever owned these
+    let mut pac = unsafe { pac::Peripherals::steal() };
+
+    // make sure we reboot, even if a display hangs
+    let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    watchdog.start(PANIC_SCREEN_US.micros());
+
+    let mut message = Buffer {
+        buf: [0; LINE_LENGTH * MESSAGE_LINES],
+        len: 0,
+    };
+    write!(message, "{}", info.message()).ok();
+
+    // stop the other core, it might be in the middle of talking to a display
+    if pac.SIO.cpuid().read().bits() == 0 {
+        pac.PSM.frce_off().modify(|_, w| w.proc1().set_bit());
+    } else {
+        pac.PSM.frce_off().modify(|_, w| w.proc0().set_bit());
+    }
+
+    let sio = Sio::new(pac.SIO);
+    let pins = Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+    let board = board::split(pins);
+
+    // try both screens, one of them is hopefully still alive
+    let (small_interface, big_interface) = board::displays(
+        board.displays,
+        Buses {
+            i2c0: pac.I2C0,
+            #[cfg(any(feature = "rp2040-zero", feature = "pico"))]
+            i2c1: pac.I2C1,
+            #[cfg(feature = "custom")]
+            spi1: pac.SPI1,
+        },
+        &mut pac.RESETS,
+        PERIPHERAL_CLOCK_MHZ.MHz(),
+    );
+
+    let mut small_display = small::init(small_interface);
+    if small_display.probe() {
+        draw(&mut small_display, info, &message);
+        small_display.flush_frame();
+    }
+
+    let mut big_display = big::init(big_interface);
+    if big_display.probe() {
+        draw(&mut big_display, info, &message);
+        big_display.flush_frame();
+    }
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
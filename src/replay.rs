@@ -0,0 +1,258 @@
+use crate::input::Button;
+use crate::seed::Seed;
+use core::mem;
+
+// a recording fills exactly one flash sector
+pub const RECORDING_SIZE: usize = 4096;
+const HEADER_SIZE: usize = 16;
+const MAX_EVENTS: usize = RECORDING_SIZE - HEADER_SIZE;
+// erased flash reads as all ones, this tells a recording apart from that
+const MAGIC: u32 = 0x5441_434f;
+
+// each event is a button in the top bits and the ticks since the previous event below
+const TICK_BITS: u32 = 6;
+const MAX_DELTA: u32 = (1 << TICK_BITS) - 1;
+// no button, only time passing
+const WAIT: u8 = 3;
+
+const SPEEDS: &[u8] = &[1, 2, 4];
+
+const fn encode(code: u8, delta: u32) -> u8 {
+    (code << TICK_BITS) | delta as u8
+}
+
+// a whole run, its seed and every button press, stored as is in flash
+#[derive(Clone)]
+#[repr(C)]
+pub struct Recording {
+    magic: u32,
+    // the newest recording has the highest number
+    number: u32,
+    seed: Seed,
+    len: u16,
+    score: u32,
+    events: [u8; MAX_EVENTS],
+}
+
+const _: () = assert!(mem::size_of::<Recording>() == RECORDING_SIZE);
+
+impl Recording {
+    pub const fn new(seed: Seed) -> Self {
+        Self {
+            magic: MAGIC,
+            number: 0,
+            seed,
+            len: 0,
+            score: 0,
+            events: [0; MAX_EVENTS],
+        }
+    }
+
+    // anything read back from flash might be garbage
+    pub const fn is_valid(&self) -> bool {
+        self.magic == MAGIC && self.len as usize <= MAX_EVENTS
+    }
+
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub const fn set_number(&mut self, number: u32) {
+        self.number = number;
+    }
+
+    pub const fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    // the score at the end of the run, a replay has to end up with the same
+    pub const fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; RECORDING_SIZE] {
+        // the struct is plain integers without any padding
+        unsafe { &*(self as *const Self as *const [u8; RECORDING_SIZE]) }
+    }
+
+    fn events(&self) -> &[u8] {
+        &self.events[..self.len as usize]
+    }
+
+    fn push(&mut self, event: u8) -> bool {
+        let Some(slot) = self.events.get_mut(self.len as usize) else {
+            return false;
+        };
+        *slot = event;
+        self.len += 1;
+        true
+    }
+}
+
+// writes the presses of a live run into a recording
+pub struct Recorder {
+    recording: Recording,
+    // ticks since the last event
+    since: u32,
+    active: bool,
+    done: bool,
+}
+
+impl Recorder {
+    pub const fn new(seed: Seed) -> Self {
+        Self {
+            recording: Recording::new(seed),
+            since: 0,
+            active: false,
+            done: false,
+        }
+    }
+
+    pub const fn start(&mut self, seed: Seed) {
+        self.recording = Recording::new(seed);
+        self.since = 0;
+        self.active = true;
+        self.done = false;
+    }
+
+    pub fn press(&mut self, button: Button, score: u32) {
+        if !self.active {
+            return;
+        }
+        // long pauses take a few events
+        while self.since > MAX_DELTA {
+            if !self.recording.push(encode(WAIT, MAX_DELTA)) {
+                return self.finish(score);
+            }
+            self.since -= MAX_DELTA;
+        }
+        if !self.recording.push(encode(button as u8, self.since)) {
+            return self.finish(score);
+        }
+        self.since = 0;
+    }
+
+    pub const fn tick(&mut self) {
+        if self.active {
+            self.since += 1;
+        }
+    }
+
+    // also called when the recording is full, the replay stops where the recording does
+    pub fn finish(&mut self, score: u32) {
+        if !self.active {
+            return;
+        }
+        self.active = false;
+        self.done = true;
+        // keep the ticks after the last press, as far as they fit
+        while self.since > 0 {
+            let delta = self.since.min(MAX_DELTA);
+            if !self.recording.push(encode(WAIT, delta)) {
+                break;
+            }
+            self.since -= delta;
+        }
+        self.recording.score = score;
+    }
+
+    // hands out a finished recording once
+    pub fn take_finished(&mut self) -> Option<&mut Recording> {
+        mem::take(&mut self.done).then_some(&mut self.recording)
+    }
+}
+
+// feeds a recording back into a game, tick by tick
+pub struct Playback {
+    recording: &'static Recording,
+    // which recording this is, counting back from the newest
+    pub index: usize,
+    position: usize,
+    // ticks since the last event
+    waited: u32,
+}
+
+impl Playback {
+    pub const fn new(recording: &'static Recording, index: usize) -> Self {
+        Self {
+            recording,
+            index,
+            position: 0,
+            waited: 0,
+        }
+    }
+
+    pub const fn seed(&self) -> Seed {
+        self.recording.seed()
+    }
+
+    pub const fn score(&self) -> u32 {
+        self.recording.score()
+    }
+
+    // the next press that happened before this tick, if any
+    pub fn press(&mut self) -> Option<Button> {
+        while let Some(&event) = self.recording.events().get(self.position) {
+            if (event as u32 & MAX_DELTA) != self.waited {
+                return None;
+            }
+            self.position += 1;
+            self.waited = 0;
+            match event >> TICK_BITS {
+                0 => return Some(Button::Action),
+                1 => return Some(Button::Up),
+                2 => return Some(Button::Down),
+                _ => (),
+            }
+        }
+        None
+    }
+
+    pub const fn tick(&mut self) {
+        self.waited += 1;
+    }
+
+    pub const fn is_done(&self) -> bool {
+        self.position >= self.recording.len as usize
+    }
+}
+
+// the playback controls, shown on top of the replay
+#[derive(Clone, Copy)]
+pub struct Controls {
+    speed: usize,
+    paused: bool,
+}
+
+impl Controls {
+    pub const fn new() -> Self {
+        Self {
+            speed: 0,
+            paused: false,
+        }
+    }
+
+    // how many ticks of the replay go into one tick of ours
+    pub const fn steps(self) -> u8 {
+        if self.paused {
+            0
+        } else {
+            SPEEDS[self.speed]
+        }
+    }
+
+    pub const fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub const fn next_speed(&mut self) {
+        self.speed = (self.speed + 1) % SPEEDS.len();
+        self.paused = false;
+    }
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,252 @@
+// const parser for RTTTL ringtones, eg. "name:d=4,o=5,b=120:8c,8e,4g."
+use crate::sound::Note;
+
+// defaults from the RTTTL spec
+const DEFAULT_DURATION: u32 = 4;
+const DEFAULT_OCTAVE: u32 = 6;
+const DEFAULT_BPM: u32 = 63;
+
+// c7 to b7, lower octaves are derived by halving
+const OCTAVE_7: [u32; 12] = [
+    2093, 2217, 2349, 2489, 2637, 2794, 2960, 3136, 3322, 3520, 3729, 3951,
+];
+
+const PAUSE: u32 = u32::MAX;
+
+struct Header {
+    duration: u32,
+    octave: u32,
+    bpm: u32,
+}
+
+// returns the position right after the next occurrence of `needle`
+const fn after(bytes: &[u8], mut i: usize, needle: u8) -> usize {
+    while i < bytes.len() {
+        i += 1;
+        if bytes[i - 1] == needle {
+            return i;
+        }
+    }
+    panic!("rtttl is missing a section separator");
+}
+
+const fn number(bytes: &[u8], mut i: usize) -> (u32, usize) {
+    let mut num = 0;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        num = num * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    (num, i)
+}
+
+const fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+const fn header(bytes: &[u8]) -> Header {
+    let mut header = Header {
+        duration: DEFAULT_DURATION,
+        octave: DEFAULT_OCTAVE,
+        bpm: DEFAULT_BPM,
+    };
+
+    let mut i = after(bytes, 0, b':');
+    loop {
+        i = skip_whitespace(bytes, i);
+        if bytes[i] == b':' {
+            return header;
+        }
+
+        let key = bytes[i].to_ascii_lowercase();
+        i = skip_whitespace(bytes, i + 1);
+        if bytes[i] != b'=' {
+            panic!("rtttl header is missing a `=`");
+        }
+        let (value, next) = number(bytes, skip_whitespace(bytes, i + 1));
+        i = skip_whitespace(bytes, next);
+
+        match key {
+            b'd' => header.duration = value,
+            b'o' => header.octave = value,
+            b'b' => header.bpm = value,
+            _ => panic!("unknown rtttl header key"),
+        }
+
+        if bytes[i] == b',' {
+            i += 1;
+        }
+    }
+}
+
+const fn frequency(semitone: u32, octave: u32) -> u32 {
+    if octave > 7 {
+        OCTAVE_7[semitone as usize] << (octave - 7)
+    } else {
+        OCTAVE_7[semitone as usize] >> (7 - octave)
+    }
+}
+
+pub const fn count(src: &str) -> usize {
+    let bytes = src.as_bytes();
+    let mut i = after(bytes, after(bytes, 0, b':'), b':');
+
+    let mut count = 0;
+    let mut in_note = false;
+    while i < bytes.len() {
+        if bytes[i] == b',' {
+            in_note = false;
+        } else if !in_note && !bytes[i].is_ascii_whitespace() {
+            in_note = true;
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+pub const fn parse<const N: usize>(src: &str) -> [Note; N] {
+    let bytes = src.as_bytes();
+    let header = header(bytes);
+    let whole_note_ms = 4 * 60_000 / header.bpm;
+
+    let mut notes = [Note::new(0, 0); N];
+    let mut i = after(bytes, after(bytes, 0, b':'), b':');
+    let mut n = 0;
+    while n < N {
+        i = skip_whitespace(bytes, i);
+
+        let (duration, next) = number(bytes, i);
+        i = next;
+        let duration = if duration == 0 {
+            header.duration
+        } else {
+            duration
+        };
+
+        let mut semitone = match bytes[i].to_ascii_lowercase() {
+            b'c' => 0,
+            b'd' => 2,
+            b'e' => 4,
+            b'f' => 5,
+            b'g' => 7,
+            b'a' => 9,
+            b'b' => 11,
+            b'p' => PAUSE,
+            _ => panic!("invalid rtttl note"),
+        };
+        i += 1;
+
+        if i < bytes.len() && bytes[i] == b'#' {
+            semitone += 1;
+            i += 1;
+        }
+
+        // the dot is allowed both before and after the octave
+        let mut dotted = false;
+        if i < bytes.len() && bytes[i] == b'.' {
+            dotted = true;
+            i += 1;
+        }
+
+        let (octave, next) = number(bytes, i);
+        i = next;
+        let mut octave = if octave == 0 { header.octave } else { octave };
+
+        if i < bytes.len() && bytes[i] == b'.' {
+            dotted = true;
+            i += 1;
+        }
+
+        let mut ms = whole_note_ms / duration;
+        if dotted {
+            ms += ms / 2;
+        }
+
+        let freq = if semitone == PAUSE {
+            0
+        } else {
+            // b# is the c of the next octave
+            if semitone == 12 {
+                semitone = 0;
+                octave += 1;
+            }
+            frequency(semitone, octave)
+        };
+        if freq > u16::MAX as u32 {
+            panic!("rtttl note is too high");
+        }
+        notes[n] = Note::new(freq as u16, ms);
+        n += 1;
+
+        i = skip_whitespace(bytes, i);
+        if i < bytes.len() && bytes[i] == b',' {
+            i += 1;
+        }
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes<const N: usize>(src: &str) -> Vec<(u16, u32)> {
+        parse::<N>(src)
+            .iter()
+            .map(|note| (note.freq, note.ms))
+            .collect()
+    }
+
+    #[test]
+    fn counts_notes() {
+        assert_eq!(count("a:d=4:c,d, e ,f"), 4);
+        assert_eq!(count("a::c"), 1);
+        assert_eq!(count("a:b=120:c, 8p ,\n4e."), 3);
+    }
+
+    #[test]
+    fn the_simpsons() {
+        const SIMPSONS: &str =
+            "The Simpsons:d=4,o=5,b=160:c.6,e6,f#6,8a6,g.6,e6,c6,8a,8f#,8f#,8f#,2g";
+        assert_eq!(
+            notes::<12>(SIMPSONS),
+            [
+                (1046, 562),
+                (1318, 375),
+                (1480, 375),
+                (1760, 187),
+                (1568, 562),
+                (1318, 375),
+                (1046, 375),
+                (880, 187),
+                (740, 187),
+                (740, 187),
+                (740, 187),
+                (784, 750),
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults_without_header() {
+        // quarter notes in the sixth octave at 63 bpm
+        assert_eq!(notes::<1>("x::a"), [(1760, 952)]);
+    }
+
+    #[test]
+    fn pauses_sharps_and_dots() {
+        assert_eq!(
+            notes::<4>("x:d=8,o=5,b=120:p,c#,b#,a.5"),
+            [(0, 250), (554, 250), (1046, 250), (880, 375)]
+        );
+    }
+
+    #[test]
+    fn long_notes_dont_wrap() {
+        // a dotted whole note at 3 bpm is two minutes long
+        assert_eq!(notes::<1>("x:d=1,o=5,b=3:c."), [(523, 120_000)]);
+    }
+}
@@ -0,0 +1,84 @@
+use rand_core::{impls, Error, RngCore};
+
+// letters that are hard to mix up on a tiny screen, one per hex digit
+const ALPHABET: [u8; DIGITS as usize] = *b"ACEFHJKLMNPRTWXY";
+pub const DIGITS: u8 = 16;
+pub const CODE_LENGTH: usize = 4;
+
+// everything random about a run follows from this, short enough to read out to a friend
+#[derive(Clone, Copy, PartialEq)]
+#[repr(transparent)]
+pub struct Seed(u16);
+
+impl Seed {
+    pub fn random<R: RngCore>(mut random: R) -> Self {
+        Self(random.next_u32() as u16)
+    }
+
+    pub const fn digit(self, index: usize) -> u8 {
+        (self.0 >> ((CODE_LENGTH - 1 - index) * 4)) as u8 & (DIGITS - 1)
+    }
+
+    pub const fn with_digit(self, index: usize, digit: u8) -> Self {
+        let shift = (CODE_LENGTH - 1 - index) * 4;
+        let mask = ((DIGITS - 1) as u16) << shift;
+        Self((self.0 & !mask) | (((digit & (DIGITS - 1)) as u16) << shift))
+    }
+
+    pub const fn code(self) -> [u8; CODE_LENGTH] {
+        let mut code = [0; CODE_LENGTH];
+        let mut index = 0;
+        while index < CODE_LENGTH {
+            code[index] = ALPHABET[self.digit(index) as usize];
+            index += 1;
+        }
+        code
+    }
+}
+
+// xorshift32, plenty for a game and it plays the same on every build
+#[derive(Clone)]
+pub struct Random {
+    state: u32,
+}
+
+impl Random {
+    pub const fn new(entropy: u32) -> Self {
+        // spread neighbouring seeds apart, xorshift gets stuck on zero
+        let mut x = entropy ^ 0x9e37_79b9;
+        x = (x ^ (x >> 16)).wrapping_mul(0x7feb_352d);
+        x = (x ^ (x >> 15)).wrapping_mul(0x846c_a68b);
+        x ^= x >> 16;
+        Self {
+            state: if x == 0 { 1 } else { x },
+        }
+    }
+
+    pub const fn from_seed(seed: Seed) -> Self {
+        Self::new(seed.0 as u32)
+    }
+}
+
+impl RngCore for Random {
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::board::hal::{
+    pac::{self, interrupt},
+    timer::{Alarm, Alarm0},
+};
+use cortex_m::peripheral::SCB;
+use fugit::MillisDurationU32;
+
+const XOSC_DORMANT: u32 = 0x636f_6d61;
+
+// only there to wake us up, `nap` checks the alarm itself
+#[interrupt]
+fn TIMER_IRQ_0() {
+    let timer = unsafe { &*pac::TIMER::ptr() };
+    timer.intr().write(|w| w.alarm_0().clear_bit_by_one());
+}
+
+// stops the core until the time is up, other interrupts get handled in between
+pub fn nap(alarm: &mut Alarm0, duration: MillisDurationU32) {
+    // only fails for durations way longer than ours
+    alarm.schedule(duration.convert()).ok();
+    while !alarm.finished() {
+        // a pending interrupt still ends the wfi, so the alarm can't fire between the check and
+        // going to sleep
+        cortex_m::interrupt::free(|_| {
+            if !alarm.finished() {
+                cortex_m::asm::wfi();
+            }
+        });
+    }
+}
+
+// stops all clocks until a gpio with dormant wake enabled fires, then reboots
+pub fn dormant() -> ! {
+    // we never return to the code that owns these peripherals
+    let pac = unsafe { pac::Peripherals::steal() };
+
+    // run everything directly from the crystal
+    pac.CLOCKS
+        .clk_ref_ctrl()
+        .modify(|_, w| w.src().xosc_clksrc());
+    while pac.CLOCKS.clk_ref_selected().read().bits() != 1 << 2 {}
+    pac.CLOCKS.clk_sys_ctrl().modify(|_, w| w.src().clk_ref());
+    while pac.CLOCKS.clk_sys_selected().read().bits() != 1 {}
+
+    // the plls are not needed anymore
+    for pll in [pac.PLL_SYS.pwr(), pac.PLL_USB.pwr()] {
+        pll.write(|w| w.pd().set_bit().postdivpd().set_bit().vcopd().set_bit());
+    }
+    // neither is the ring oscillator, it only ran for the seeds
+    pac.ROSC.ctrl().modify(|_, w| w.enable().disable());
+
+    unsafe {
+        pac.XOSC.dormant().write(|w| w.bits(XOSC_DORMANT));
+    }
+    while pac.XOSC.status().read().stable().bit_is_clear() {}
+
+    // the boot rom runs from the ring oscillator
+    pac.ROSC.ctrl().modify(|_, w| w.enable().enable());
+    while pac.ROSC.status().read().stable().bit_is_clear() {}
+
+    // start over with properly configured clocks
+    SCB::sys_reset()
+}
@@ -0,0 +1,255 @@
+use crate::game::{Event, TICK_MS};
+use crate::tunes;
+use core::ptr;
+
+// anything that can play a square wave at a given frequency
+pub trait Tone {
+    fn play(&mut self, freq: u16);
+    fn stop(&mut self);
+}
+
+// the pwm period for a frequency, given how fast the pwm counter runs
+pub fn pwm_top(counter_freq: u32, freq: u16) -> u16 {
+    (counter_freq / freq as u32).clamp(2, u16::MAX as u32) as u16
+}
+
+#[derive(Clone, Copy)]
+pub struct Note {
+    // zero is a rest
+    pub freq: u16,
+    // whole notes at a slow tempo take longer than a u16 of milliseconds
+    pub ms: u32,
+}
+
+impl Note {
+    pub const fn new(freq: u16, ms: u32) -> Self {
+        Self { freq, ms }
+    }
+}
+
+pub const PIN_SET: &[Note] = &[Note::new(1319, 50)];
+pub const PIN_SLIPPED: &[Note] = &[Note::new(147, 100)];
+pub const GOAL_REACHED: &[Note] = &[Note::new(784, 100), Note::new(0, 50), Note::new(1047, 150)];
+pub const TURN: &[Note] = &[Note::new(523, 50)];
+pub const GAME_OVER: &[Note] = &[
+    Note::new(392, 150),
+    Note::new(330, 150),
+    Note::new(262, 300),
+];
+
+pub const fn effect(event: Event) -> &'static [Note] {
+    match event {
+        Event::PinSet => PIN_SET,
+        Event::PinSlipped => PIN_SLIPPED,
+        Event::LockOpened => &tunes::DELIVERY,
+        Event::GoalReached => GOAL_REACHED,
+        Event::Turn => TURN,
+        Event::GameOver => GAME_OVER,
+    }
+}
+
+// a single voice, moving from note to note every tick
+struct Track {
+    notes: &'static [Note],
+    looping: bool,
+    next: usize,
+    remaining_ms: u32,
+    freq: u16,
+}
+
+enum Step {
+    Hold,
+    Note(u16),
+    Done,
+}
+
+impl Track {
+    const EMPTY: Self = Self::new(&[], false);
+
+    const fn new(notes: &'static [Note], looping: bool) -> Self {
+        Self {
+            notes,
+            looping,
+            next: 0,
+            remaining_ms: 0,
+            freq: 0,
+        }
+    }
+
+    const fn is_active(&self) -> bool {
+        self.remaining_ms > 0 || self.next < self.notes.len()
+    }
+
+    fn tick(&mut self) -> Step {
+        if self.remaining_ms > TICK_MS {
+            self.remaining_ms -= TICK_MS;
+            return Step::Hold;
+        }
+
+        // carry over the time we've overshot, so long tunes stay in tempo
+        let carry = if self.remaining_ms > 0 {
+            TICK_MS - self.remaining_ms
+        } else {
+            // nothing was playing, e.g. the very first note
+            0
+        };
+        if self.looping && self.next >= self.notes.len() {
+            self.next = 0;
+        }
+
+        let Some(note) = self.notes.get(self.next) else {
+            self.remaining_ms = 0;
+            self.freq = 0;
+            return Step::Done;
+        };
+        self.next += 1;
+        self.remaining_ms = note.ms.saturating_sub(carry);
+        self.freq = note.freq;
+        Step::Note(note.freq)
+    }
+}
+
+pub struct Sequencer<T: Tone> {
+    tone: T,
+    effect: Track,
+    music: Track,
+}
+
+impl<T: Tone> Sequencer<T> {
+    pub const fn new(tone: T) -> Self {
+        Self {
+            tone,
+            effect: Track::EMPTY,
+            music: Track::EMPTY,
+        }
+    }
+
+    // replaces the current effect, the first note starts on the next tick
+    pub const fn play(&mut self, notes: &'static [Note]) {
+        self.effect = Track::new(notes, false);
+    }
+
+    pub const fn event(&mut self, event: Event) {
+        self.play(effect(event));
+    }
+
+    // loops the tune in the background, restarting only if it's a different one
+    pub fn music(&mut self, notes: Option<&'static [Note]>) {
+        match notes {
+            Some(notes) if ptr::eq(self.music.notes, notes) => (),
+            Some(notes) => self.music = Track::new(notes, true),
+            None => self.music = Track::EMPTY,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        // effects take priority, the music is paused meanwhile
+        let step = if self.effect.is_active() {
+            match self.effect.tick() {
+                Step::Done => Step::Note(self.music.freq),
+                step => step,
+            }
+        } else {
+            self.music.tick()
+        };
+
+        match step {
+            Step::Hold => (),
+            Step::Note(0) | Step::Done => self.tone.stop(),
+            Step::Note(freq) => self.tone.play(freq),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the buzzer runs its counter at 125 MHz / 40 / 2
+    const COUNTER_FREQ: u32 = 1_562_500;
+
+    // a pwm slice like the one the buzzer drives, it only remembers its registers
+    #[derive(Default)]
+    struct MockPwm {
+        top: u16,
+        duty: u16,
+    }
+
+    impl MockPwm {
+        // what comes out of the pin, none while silent
+        fn freq(&self) -> Option<u32> {
+            (self.duty > 0).then(|| COUNTER_FREQ / (self.top as u32 + 1))
+        }
+    }
+
+    impl Tone for MockPwm {
+        fn play(&mut self, freq: u16) {
+            let top = pwm_top(COUNTER_FREQ, freq);
+            self.top = top - 1;
+            self.duty = top / 2;
+        }
+
+        fn stop(&mut self) {
+            self.duty = 0;
+        }
+    }
+
+    const A: &[Note] = &[Note::new(440, 1000)];
+    const A_AND_REST: &[Note] = &[Note::new(440, 50), Note::new(0, 50)];
+
+    fn run(sequencer: &mut Sequencer<MockPwm>, ticks: usize) -> Vec<Option<u32>> {
+        (0..ticks)
+            .map(|_| {
+                sequencer.tick();
+                sequencer.tone.freq()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn effect_plays_each_note_for_its_length() {
+        let mut sequencer = Sequencer::new(MockPwm::default());
+        sequencer.event(Event::GoalReached);
+        // 100 ms of g, 50 ms rest, 150 ms of c
+        assert_eq!(
+            run(&mut sequencer, 8),
+            [
+                Some(784),
+                Some(784),
+                None,
+                Some(1047),
+                Some(1047),
+                Some(1047),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn effect_pauses_music() {
+        let mut sequencer = Sequencer::new(MockPwm::default());
+        sequencer.music(Some(A));
+        assert_eq!(run(&mut sequencer, 2), [Some(440), Some(440)]);
+
+        sequencer.event(Event::PinSet);
+        assert_eq!(run(&mut sequencer, 3), [Some(1319), Some(440), Some(440)]);
+
+        sequencer.music(None);
+        assert_eq!(run(&mut sequencer, 1), [None]);
+    }
+
+    #[test]
+    fn music_loops() {
+        let mut sequencer = Sequencer::new(MockPwm::default());
+        sequencer.music(Some(A_AND_REST));
+        assert_eq!(run(&mut sequencer, 4), [Some(440), None, Some(440), None]);
+    }
+
+    #[test]
+    fn pwm_top_fits_the_counter() {
+        assert_eq!(pwm_top(COUNTER_FREQ, 1), u16::MAX);
+        assert_eq!(pwm_top(COUNTER_FREQ, u16::MAX), 23);
+        assert_eq!(pwm_top(100, 100), 2);
+    }
+}
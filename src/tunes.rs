@@ -0,0 +1,8 @@
+use crate::rtttl;
+use crate::sound::Note;
+
+const START_RTTTL: &str = "taco:d=8,o=5,b=150:c,e,g,c6,4p,a,g,e,4d,4p,c,e,g,a,g,e,4d.,c,2c,4p";
+pub static START: [Note; rtttl::count(START_RTTTL)] = rtttl::parse(START_RTTTL);
+
+const DELIVERY_RTTTL: &str = "delivery:d=16,o=6,b=180:c,e,g,c7,p,g,4c7";
+pub static DELIVERY: [Note; rtttl::count(DELIVERY_RTTTL)] = rtttl::parse(DELIVERY_RTTTL);
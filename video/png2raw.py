@@ -0,0 +1,75 @@
+#!/usr/bin/env python3
+# turns a sprite sheet png into the 1 bit raw image the firmware includes, one bit per pixel,
+# rows padded to full bytes, the leftmost pixel in the highest bit, bright pixels are on
+#
+#   python3 video/png2raw.py video/bike.png video/bike-sheet.raw
+#
+# only needs the standard library, takes 8 bit gray, gray with alpha, rgb and rgba pngs
+import struct
+import sys
+import zlib
+
+CHANNELS = {0: 1, 2: 3, 4: 2, 6: 4}
+
+
+def paeth(a, b, c):
+    p = a + b - c
+    pa, pb, pc = abs(p - a), abs(p - b), abs(p - c)
+    if pa <= pb and pa <= pc:
+        return a
+    return b if pb <= pc else c
+
+
+def read_png(path):
+    data = open(path, 'rb').read()
+    assert data[:8] == b'\x89PNG\r\n\x1a\n', 'not a png'
+    pos, idat = 8, b''
+    while pos < len(data):
+        (length,) = struct.unpack('>I', data[pos:pos + 4])
+        kind, chunk = data[pos + 4:pos + 8], data[pos + 8:pos + 8 + length]
+        pos += 12 + length
+        if kind == b'IHDR':
+            width, height, depth, color, _, _, interlace = struct.unpack('>IIBBBBB', chunk)
+            assert depth == 8 and color in CHANNELS and not interlace, 'unsupported png'
+        elif kind == b'IDAT':
+            idat += chunk
+
+    channels = CHANNELS[color]
+    stride = width * channels
+    raw = zlib.decompress(idat)
+    rows, prev = [], bytearray(stride)
+    for y in range(height):
+        kind = raw[y * (stride + 1)]
+        line = bytearray(raw[y * (stride + 1) + 1:(y + 1) * (stride + 1)])
+        for i in range(stride):
+            a = line[i - channels] if i >= channels else 0
+            b = prev[i]
+            c = prev[i - channels] if i >= channels else 0
+            line[i] = (line[i] + [0, a, b, (a + b) // 2, paeth(a, b, c)][kind]) & 0xff
+        rows.append([line[x * channels:(x + 1) * channels] for x in range(width)])
+        prev = line
+    return width, rows
+
+
+def lit(pixel):
+    # transparent counts as off
+    if len(pixel) in (2, 4) and pixel[-1] < 128:
+        return False
+    color = pixel[:3] if len(pixel) >= 3 else pixel[:1]
+    return sum(color) / len(color) >= 128
+
+
+def main(src, dst):
+    width, rows = read_png(src)
+    out = bytearray()
+    for row in rows:
+        for left in range(0, width, 8):
+            byte = 0
+            for x in range(left, left + 8):
+                byte = byte << 1 | (x < width and lit(row[x]))
+            out.append(byte)
+    open(dst, 'wb').write(out)
+
+
+if __name__ == '__main__':
+    main(*sys.argv[1:])